@@ -0,0 +1,91 @@
+use log::{info, warn};
+use std::collections::HashMap;
+
+use crate::analysis::perceptual_hash::PerceptualHash;
+use crate::file_io::file_operations::ScreenshotRecord;
+
+/// 默认的聚类距离阈值（64 位哈希中不同的位数）
+pub const DEFAULT_CLUSTER_THRESHOLD: u32 = 10;
+
+/// 按感知哈希归并外观相似的截图
+pub struct ScreenshotClusters;
+
+impl ScreenshotClusters {
+    /// 为每个目标的最后一条成功记录分配 `cluster_id` 和 `cluster_size`，返回分组数
+    ///
+    /// 每组以第一张截图为代表，与代表的汉明距离不超过 `threshold` 的截图归入该组，
+    /// 避免链式合并把差异很大的页面连成一组。缺少哈希的旧记录会从截图文件补算。
+    /// 同一目标的较早记录不参与聚类，其分组字段被清空。
+    pub fn assign(records: &mut [ScreenshotRecord], threshold: u32) -> usize {
+        let mut latest: HashMap<&str, usize> = HashMap::new();
+        for (index, record) in records.iter().enumerate() {
+            latest.insert(&record.target, index);
+        }
+        let mut latest: Vec<usize> = latest.into_values().collect();
+        latest.sort_unstable();
+
+        for record in records.iter_mut() {
+            record.cluster_id = None;
+            record.cluster_size = None;
+        }
+
+        // (代表哈希, 成员下标)
+        let mut clusters: Vec<(u64, Vec<usize>)> = Vec::new();
+        for index in latest {
+            let Some(hash) = Self::hash_of(&mut records[index]) else {
+                continue;
+            };
+
+            match clusters.iter_mut().find(|(representative, _)| {
+                PerceptualHash::distance(*representative, hash) <= threshold
+            }) {
+                Some((_, members)) => members.push(index),
+                None => clusters.push((hash, vec![index])),
+            }
+        }
+
+        for (cluster_id, (_, members)) in clusters.iter().enumerate() {
+            for &index in members {
+                records[index].cluster_id = Some(cluster_id + 1);
+                records[index].cluster_size = Some(members.len());
+            }
+        }
+
+        let grouped = clusters
+            .iter()
+            .filter(|(_, members)| members.len() > 1)
+            .count();
+        info!(
+            "截图聚类完成: {} 个分组，其中 {} 个包含多张相似截图",
+            clusters.len(),
+            grouped
+        );
+        clusters.len()
+    }
+
+    /// 取记录的哈希，没有时尝试从截图文件计算并回填
+    fn hash_of(record: &mut ScreenshotRecord) -> Option<u64> {
+        if !record.success {
+            return None;
+        }
+        if let Some(hash) = record
+            .perceptual_hash
+            .as_deref()
+            .and_then(PerceptualHash::from_hex)
+        {
+            return Some(hash);
+        }
+
+        let path = record.screenshot_path.as_deref()?;
+        match PerceptualHash::from_file(path) {
+            Ok(hash) => {
+                record.perceptual_hash = Some(PerceptualHash::to_hex(hash));
+                Some(hash)
+            }
+            Err(e) => {
+                warn!("无法计算截图哈希 {}: {}", path, e);
+                None
+            }
+        }
+    }
+}
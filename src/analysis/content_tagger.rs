@@ -0,0 +1,163 @@
+use crate::models::content_tag::ContentTag;
+use crate::models::page_content::PageContent;
+
+/// 登录页标题中的常见词（小写）
+const LOGIN_TITLES: &[&str] = &["login", "log in", "sign in", "signin", "登录", "登陆"];
+
+/// 常见管理后台和设备管理界面的标题特征（小写）
+const ADMIN_PANEL_TITLES: &[&str] = &[
+    "phpmyadmin",
+    "adminer",
+    "webmin",
+    "cpanel",
+    "plesk",
+    "tomcat web application manager",
+    "weblogic server administration console",
+    "jboss",
+    "wildfly",
+    "jenkins",
+    "portainer",
+    "rabbitmq management",
+    "nexus repository manager",
+    "sonarqube",
+    "django site admin",
+    "site administration",
+    "admin console",
+    "control panel",
+    "pfsense",
+    "opnsense",
+    "routeros",
+    "openwrt",
+    "synology",
+    "qnap",
+    "fortigate",
+    "sonicwall",
+    "unifi",
+    "管理后台",
+    "后台管理",
+    "管理系统",
+];
+
+/// 常见监控、运维仪表盘的标题特征（小写）
+const DASHBOARD_TITLES: &[&str] = &[
+    "grafana",
+    "kibana",
+    "prometheus time series",
+    "alertmanager",
+    "jupyter",
+    "kubernetes dashboard",
+    "traefik",
+    "consul by hashicorp",
+    "netdata",
+    "zabbix",
+    "nagios",
+    "airflow",
+    "argo cd",
+    "spark master",
+    "uptime kuma",
+];
+
+/// 目录列表页的标题或正文开头（小写）
+const DIRECTORY_LISTING_PREFIXES: &[&str] = &["index of /", "directory listing for /"];
+
+/// 提示默认账号密码的文本（小写）
+const DEFAULT_CREDENTIAL_HINTS: &[&str] = &[
+    "default password",
+    "default username",
+    "default credentials",
+    "default login",
+    "admin/admin",
+    "admin:admin",
+    "默认密码",
+    "默认用户名",
+    "默认账号",
+    "初始密码",
+];
+
+/// 根据页面标题和内容标出值得关注的页面
+pub struct ContentTagger;
+
+impl ContentTagger {
+    /// 返回页面命中的全部标签，按固定顺序排列
+    pub fn tag(title: Option<&str>, content: &PageContent) -> Vec<ContentTag> {
+        let title = title.unwrap_or_default().to_lowercase();
+        let text = content.text.to_lowercase();
+        let title_matches = |signatures: &[&str]| signatures.iter().any(|s| title.contains(s));
+
+        let mut tags = Vec::new();
+        if content.password_fields > 0 || title_matches(LOGIN_TITLES) {
+            tags.push(ContentTag::Login);
+        }
+        if title_matches(ADMIN_PANEL_TITLES) {
+            tags.push(ContentTag::AdminPanel);
+        }
+        if DIRECTORY_LISTING_PREFIXES
+            .iter()
+            .any(|prefix| title.starts_with(prefix) || text.trim_start().starts_with(prefix))
+            || text.contains("[to parent directory]")
+        {
+            tags.push(ContentTag::DirectoryListing);
+        }
+        if title_matches(DASHBOARD_TITLES) {
+            tags.push(ContentTag::Dashboard);
+        }
+        if DEFAULT_CREDENTIAL_HINTS
+            .iter()
+            .any(|hint| text.contains(hint))
+        {
+            tags.push(ContentTag::DefaultCredentials);
+        }
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(text: &str, password_fields: usize) -> PageContent {
+        PageContent {
+            text: text.to_string(),
+            password_fields,
+            ..PageContent::default()
+        }
+    }
+
+    #[test]
+    fn tags_follow_title_text_and_form_rules() {
+        // 密码框和管理后台标题，页面提示默认密码
+        let tags = ContentTagger::tag(Some("phpMyAdmin"), &content("Default password is admin", 1));
+        assert_eq!(
+            tags,
+            [
+                ContentTag::Login,
+                ContentTag::AdminPanel,
+                ContentTag::DefaultCredentials
+            ]
+        );
+
+        assert_eq!(
+            ContentTagger::tag(Some("Index of /backup"), &content("", 0)),
+            [ContentTag::DirectoryListing]
+        );
+        assert_eq!(
+            ContentTagger::tag(None, &content("  Directory listing for /files", 0)),
+            [ContentTag::DirectoryListing]
+        );
+        assert_eq!(
+            ContentTagger::tag(Some("Grafana"), &content("", 0)),
+            [ContentTag::Dashboard]
+        );
+        assert_eq!(
+            ContentTagger::tag(Some("用户登录"), &content("", 0)),
+            [ContentTag::Login]
+        );
+    }
+
+    #[test]
+    fn ordinary_pages_get_no_tags() {
+        // 正文中间出现 "index of /" 不算目录列表，后台名称只看标题
+        let text = "Read our guide to the index of / paths and Jenkins pipelines.";
+        assert!(ContentTagger::tag(Some("Blog"), &content(text, 0)).is_empty());
+    }
+}
@@ -0,0 +1,434 @@
+use log::{info, warn};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use crate::models::page_content::PageContent;
+use crate::models::page_response::PageResponse;
+use crate::models::screenshot_error::ScreenshotError;
+use crate::models::technology::Technology;
+
+/// 内置指纹规则（Wappalyzer 格式的子集）
+const BUNDLED_RULES: &str = include_str!("rules/technologies.json");
+
+/// 规则文件顶层结构
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    technologies: BTreeMap<String, RawRule>,
+}
+
+/// 规则文件中的单个技术
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawRule {
+    /// 分类名称，Wappalyzer 的数字分类ID按原样保存为字符串
+    cats: Vec<serde_json::Value>,
+    headers: BTreeMap<String, Patterns>,
+    cookies: BTreeMap<String, Patterns>,
+    meta: BTreeMap<String, Patterns>,
+    #[serde(rename = "scriptSrc")]
+    script_src: Patterns,
+    html: Patterns,
+    implies: Patterns,
+}
+
+/// 规则中的一个或多个模式
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Patterns {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for Patterns {
+    fn default() -> Self {
+        Patterns::Many(Vec::new())
+    }
+}
+
+impl Patterns {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            Patterns::One(pattern) => vec![pattern],
+            Patterns::Many(patterns) => patterns,
+        }
+    }
+}
+
+/// 编译后的模式，`\;version:\1` 后缀给出版本号的提取方式
+struct Pattern {
+    regex: Regex,
+    version: Option<String>,
+}
+
+impl Pattern {
+    fn compile(text: &str) -> Result<Self, regex::Error> {
+        let mut parts = text.split("\\;");
+        let regex = RegexBuilder::new(parts.next().unwrap_or_default())
+            .case_insensitive(true)
+            .build()?;
+        let version = parts
+            .filter_map(|part| part.strip_prefix("version:"))
+            .map(str::to_string)
+            .next();
+        Ok(Self { regex, version })
+    }
+
+    /// 匹配成功时返回提取到的版本号（可能没有）
+    fn matches(&self, value: &str) -> Option<Option<String>> {
+        let captures = self.regex.captures(value)?;
+        let Some(template) = &self.version else {
+            return Some(None);
+        };
+
+        let mut version = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek().and_then(|next| next.to_digit(10))) {
+                ('\\', Some(group)) => {
+                    chars.next();
+                    if let Some(text) = captures.get(group as usize) {
+                        version.push_str(text.as_str());
+                    }
+                }
+                _ => version.push(c),
+            }
+        }
+        Some(Some(version).filter(|version| !version.is_empty()))
+    }
+}
+
+/// 编译后的技术规则
+struct Rule {
+    name: String,
+    categories: Vec<String>,
+    headers: Vec<(String, Vec<Pattern>)>,
+    cookies: Vec<(String, Vec<Pattern>)>,
+    meta: Vec<(String, Vec<Pattern>)>,
+    script_src: Vec<Pattern>,
+    html: Vec<Pattern>,
+    implies: Vec<String>,
+}
+
+impl Rule {
+    fn compile(name: String, raw: RawRule) -> Self {
+        let compile_all = |patterns: Patterns| -> Vec<Pattern> {
+            patterns
+                .into_vec()
+                .iter()
+                .filter_map(|pattern| match Pattern::compile(pattern) {
+                    Ok(pattern) => Some(pattern),
+                    Err(e) => {
+                        warn!("指纹规则 {} 的模式无效，已忽略 {}: {}", name, pattern, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+        let compile_map = |map: BTreeMap<String, Patterns>, lowercase: bool| {
+            map.into_iter()
+                .map(|(key, patterns)| {
+                    let key = if lowercase { key.to_lowercase() } else { key };
+                    (key, compile_all(patterns))
+                })
+                .collect()
+        };
+
+        Self {
+            categories: raw
+                .cats
+                .into_iter()
+                .map(|category| match category {
+                    serde_json::Value::String(category) => category,
+                    other => other.to_string(),
+                })
+                .collect(),
+            headers: compile_map(raw.headers, true),
+            cookies: compile_map(raw.cookies, false),
+            meta: compile_map(raw.meta, true),
+            script_src: compile_all(raw.script_src),
+            html: compile_all(raw.html),
+            // 隐含关系也可能带 `\;confidence:50` 之类的后缀
+            implies: raw
+                .implies
+                .into_vec()
+                .into_iter()
+                .filter_map(|implied| implied.split("\\;").next().map(str::to_string))
+                .collect(),
+            name,
+        }
+    }
+
+    /// 匹配响应和页面内容，匹配成功时返回提取到的版本号（可能没有）
+    fn detect(&self, response: &PageResponse, content: &PageContent) -> Option<Option<String>> {
+        let mut matches = Self::keyed_matches(&self.headers, &response.headers)
+            .chain(Self::keyed_matches(&self.cookies, &content.cookies))
+            .chain(Self::keyed_matches(&self.meta, &content.meta))
+            .chain(Self::first_match(
+                &self.script_src,
+                content.script_srcs.iter().map(String::as_str),
+            ))
+            .chain(Self::first_match(
+                &self.html,
+                std::iter::once(content.html.as_str()),
+            ));
+
+        let first = matches.next()?;
+        // 优先使用带版本号的匹配
+        Some(first.or_else(|| matches.find_map(|version| version)))
+    }
+
+    /// 按名称取值后匹配，名称不存在时不算匹配
+    fn keyed_matches<'a>(
+        rules: &'a [(String, Vec<Pattern>)],
+        values: &'a BTreeMap<String, String>,
+    ) -> impl Iterator<Item = Option<String>> + 'a {
+        rules.iter().filter_map(|(key, patterns)| {
+            let value = values.get(key)?;
+            Self::first_match(patterns, std::iter::once(value.as_str()))
+        })
+    }
+
+    fn first_match<'a>(
+        patterns: &[Pattern],
+        values: impl Iterator<Item = &'a str> + Clone,
+    ) -> Option<Option<String>> {
+        let mut found = None;
+        for pattern in patterns {
+            for value in values.clone() {
+                match pattern.matches(value) {
+                    Some(Some(version)) => return Some(Some(version)),
+                    Some(None) => found = Some(None),
+                    None => {}
+                }
+            }
+        }
+        found
+    }
+}
+
+/// 根据响应头、Cookie、meta 标签、脚本地址和 HTML 识别网站使用的技术
+///
+/// 规则采用 Wappalyzer 的 JSON 格式：`headers`、`cookies`、`meta` 为名称到正则的映射，
+/// `scriptSrc`、`html` 为正则或正则数组，`implies` 给出同时认定的其他技术。
+/// 正则不区分大小写，空正则表示只要求名称存在。
+pub struct FingerprintEngine {
+    rules: Vec<Rule>,
+}
+
+impl FingerprintEngine {
+    /// 只使用内置规则
+    pub fn bundled() -> Self {
+        let rules: RulesFile = serde_json::from_str(BUNDLED_RULES).expect("内置指纹规则格式错误");
+        Self::compile(rules.technologies)
+    }
+
+    /// 加载内置规则，并用 `extra_rules` 文件中的规则覆盖或补充同名技术
+    pub fn load(extra_rules: Option<&str>) -> Result<Self, ScreenshotError> {
+        let Some(path) = extra_rules else {
+            return Ok(Self::bundled());
+        };
+
+        let mut technologies = serde_json::from_str::<RulesFile>(BUNDLED_RULES)
+            .expect("内置指纹规则格式错误")
+            .technologies;
+        let text = fs::read_to_string(path)
+            .map_err(|e| ScreenshotError::Other(format!("读取指纹规则文件失败 {}: {}", path, e)))?;
+        let extra: RulesFile = serde_json::from_str(&text)
+            .map_err(|e| ScreenshotError::Other(format!("指纹规则文件格式错误 {}: {}", path, e)))?;
+        info!("加载指纹规则 {} 条: {}", extra.technologies.len(), path);
+        technologies.extend(extra.technologies);
+
+        Ok(Self::compile(technologies))
+    }
+
+    fn compile(technologies: BTreeMap<String, RawRule>) -> Self {
+        Self {
+            rules: technologies
+                .into_iter()
+                .map(|(name, raw)| Rule::compile(name, raw))
+                .collect(),
+        }
+    }
+
+    /// 识别页面使用的技术，包含规则中隐含的技术，按名称排序（不区分大小写）
+    pub fn detect(&self, response: &PageResponse, content: &PageContent) -> Vec<Technology> {
+        let by_name: HashMap<&str, &Rule> = self
+            .rules
+            .iter()
+            .map(|rule| (rule.name.as_str(), rule))
+            .collect();
+
+        let mut detected: BTreeMap<String, Technology> = BTreeMap::new();
+        let mut pending: Vec<&str> = Vec::new();
+        for rule in &self.rules {
+            if let Some(version) = rule.detect(response, content) {
+                detected.insert(
+                    rule.name.clone(),
+                    Technology {
+                        name: rule.name.clone(),
+                        version,
+                        categories: rule.categories.clone(),
+                    },
+                );
+                pending.extend(rule.implies.iter().map(String::as_str));
+            }
+        }
+
+        while let Some(name) = pending.pop() {
+            if detected.contains_key(name) {
+                continue;
+            }
+            let rule = by_name.get(name);
+            detected.insert(
+                name.to_string(),
+                Technology {
+                    name: name.to_string(),
+                    version: None,
+                    categories: rule.map(|rule| rule.categories.clone()).unwrap_or_default(),
+                },
+            );
+            if let Some(rule) = rule {
+                pending.extend(rule.implies.iter().map(String::as_str));
+            }
+        }
+
+        let mut technologies: Vec<Technology> = detected.into_values().collect();
+        technologies.sort_by_key(|technology| technology.name.to_lowercase());
+        technologies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(headers: &[(&str, &str)]) -> PageResponse {
+        PageResponse {
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            ..PageResponse::default()
+        }
+    }
+
+    fn names(technologies: &[Technology]) -> Vec<(&str, Option<&str>)> {
+        technologies
+            .iter()
+            .map(|technology| (technology.name.as_str(), technology.version.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn versions_are_extracted_from_the_template() {
+        let pattern = Pattern::compile(r"^(\d+)_(\d+)$\;version:\1.\2-lts").unwrap();
+        assert_eq!(pattern.matches("6_4"), Some(Some("6.4-lts".to_string())));
+        assert_eq!(pattern.matches("x"), None);
+
+        // 可选分组没有匹配到时不给出版本号
+        let engine = FingerprintEngine::bundled();
+        let detected = engine.detect(&response(&[("server", "nginx")]), &PageContent::default());
+        assert_eq!(names(&detected), [("nginx", None)]);
+
+        let detected = engine.detect(
+            &response(&[("server", "nginx/1.25.3")]),
+            &PageContent::default(),
+        );
+        assert_eq!(names(&detected), [("nginx", Some("1.25.3"))]);
+    }
+
+    #[test]
+    fn implied_technologies_are_followed_transitively() {
+        let content = PageContent {
+            cookies: BTreeMap::from([("_gitlab_session".to_string(), "abc".to_string())]),
+            ..PageContent::default()
+        };
+        let detected = FingerprintEngine::bundled().detect(&PageResponse::default(), &content);
+
+        assert_eq!(
+            names(&detected),
+            [("GitLab", None), ("Ruby", None), ("Ruby on Rails", None)]
+        );
+        // 隐含的技术使用其自身规则中的分类
+        assert_eq!(detected[1].categories, ["Programming languages"]);
+    }
+
+    #[test]
+    fn keyed_rules_require_the_named_header_cookie_or_meta() {
+        let engine = FingerprintEngine::bundled();
+
+        // 空模式只要求名称存在；其他响应头中出现同样的值不算匹配
+        let detected = engine.detect(&response(&[("cf-ray", "")]), &PageContent::default());
+        assert_eq!(names(&detected), [("Cloudflare", None)]);
+        let detected = engine.detect(
+            &response(&[("x-note", "cloudflare")]),
+            &PageContent::default(),
+        );
+        assert!(detected.is_empty(), "{:?}", detected);
+
+        // Cookie 名称区分大小写
+        let cookie = |name: &str| PageContent {
+            cookies: BTreeMap::from([(name.to_string(), "1".to_string())]),
+            ..PageContent::default()
+        };
+        let detected = engine.detect(&PageResponse::default(), &cookie("PHPSESSID"));
+        assert_eq!(names(&detected), [("PHP", None)]);
+        let detected = engine.detect(&PageResponse::default(), &cookie("phpsessid"));
+        assert!(detected.is_empty(), "{:?}", detected);
+
+        let content = PageContent {
+            meta: BTreeMap::from([("generator".to_string(), "WordPress 6.4.2".to_string())]),
+            ..PageContent::default()
+        };
+        let detected = engine.detect(&PageResponse::default(), &content);
+        assert_eq!(
+            names(&detected),
+            [("MySQL", None), ("PHP", None), ("WordPress", Some("6.4.2"))]
+        );
+    }
+
+    #[test]
+    fn user_rules_override_bundled_rules() {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-rules-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rules.json");
+        fs::write(
+            &path,
+            r#"{
+                "technologies": {
+                    "nginx": { "cats": ["Gateway"], "headers": { "x-gateway": "" } },
+                    "Internal Portal": {
+                        "cats": [42],
+                        "html": "<title>Portal v([\\d.]+)\\;version:\\1",
+                        "implies": "nginx\\;confidence:50"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let engine = FingerprintEngine::load(Some(&path.to_string_lossy())).unwrap();
+
+        // 同名规则被整条替换，内置的 server 头规则不再生效
+        let detected = engine.detect(
+            &response(&[("server", "nginx/1.25.3")]),
+            &PageContent::default(),
+        );
+        assert!(detected.is_empty(), "{:?}", detected);
+
+        let content = PageContent {
+            html: "<html><title>Portal v2.1</title></html>".to_string(),
+            ..PageContent::default()
+        };
+        let detected = engine.detect(&PageResponse::default(), &content);
+        assert_eq!(
+            names(&detected),
+            [("Internal Portal", Some("2.1")), ("nginx", None)]
+        );
+        assert_eq!(detected[0].categories, ["42"]);
+        assert_eq!(detected[1].categories, ["Gateway"]);
+
+        assert!(FingerprintEngine::load(Some("/nonexistent/rules.json")).is_err());
+    }
+}
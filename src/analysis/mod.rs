@@ -0,0 +1,15 @@
+//! 截图分析模块
+//!
+//! 包含感知哈希、相似截图聚类、页面分类、内容标签、技术指纹识别和运行间截图对比功能
+
+pub mod clustering;
+pub mod content_tagger;
+pub mod fingerprint;
+pub mod page_classifier;
+pub mod perceptual_hash;
+pub mod run_diff;
+pub mod visual_diff;
+
+pub use clustering::ScreenshotClusters;
+pub use fingerprint::FingerprintEngine;
+pub use perceptual_hash::PerceptualHash;
@@ -0,0 +1,175 @@
+use image::DynamicImage;
+
+use crate::models::page_class::PageClass;
+use crate::models::page_content::PageContent;
+use crate::screenshot::address_bar::ADDRESS_BAR_HEIGHT;
+
+/// 灰度方差低于该值视为空白页
+const BLANK_VARIANCE_THRESHOLD: f64 = 25.0;
+
+/// 常见 Web 服务器默认页、停放域名和建设中页面的特征文本（小写）
+const DEFAULT_PAGE_SIGNATURES: &[&str] = &[
+    "welcome to nginx",
+    "test page for the nginx http server",
+    "it works!",
+    "apache2 ubuntu default page",
+    "apache2 debian default page",
+    "test page for the apache http server",
+    "iis windows server",
+    "internet information services",
+    "welcome to centos",
+    "if you're seeing this, you've successfully installed tomcat",
+    "caddy works!",
+    "default web site page",
+    "future home of something quite cool",
+    "this domain is for sale",
+    "this domain may be for sale",
+    "buy this domain",
+    "domain is parked",
+    "parked free",
+    "website is under construction",
+    "site under construction",
+    "coming soon",
+];
+
+/// 截图后的页面分类
+pub struct PageClassifier;
+
+impl PageClassifier {
+    /// 依次判断 Chrome 错误页、空白页和默认页，都不符合时为正常页面
+    pub fn classify(
+        image: Option<&DynamicImage>,
+        title: Option<&str>,
+        content: &PageContent,
+    ) -> PageClass {
+        if content.chrome_error {
+            return PageClass::ErrorPage;
+        }
+        if image.is_some_and(Self::is_blank_image) {
+            return PageClass::Blank;
+        }
+        if Self::matches_default_page(title, &content.text) {
+            return PageClass::DefaultPage;
+        }
+        PageClass::Normal
+    }
+
+    /// 根据灰度方差判断截图是否几乎没有内容
+    pub fn is_blank_image(image: &DynamicImage) -> bool {
+        if image.height() <= ADDRESS_BAR_HEIGHT {
+            return true;
+        }
+
+        // 跳过顶部的地址栏；缩小后计算，方差对缩放不敏感，且避免逐像素处理整张截图
+        let page = image
+            .crop_imm(
+                0,
+                ADDRESS_BAR_HEIGHT,
+                image.width(),
+                image.height() - ADDRESS_BAR_HEIGHT,
+            )
+            .thumbnail(128, 256)
+            .to_luma8();
+
+        let count = page.pixels().len() as f64;
+        if count == 0.0 {
+            return true;
+        }
+        let mean = page.pixels().map(|p| f64::from(p[0])).sum::<f64>() / count;
+        let variance = page
+            .pixels()
+            .map(|p| (f64::from(p[0]) - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        variance < BLANK_VARIANCE_THRESHOLD
+    }
+
+    /// 标题或页面文本是否包含默认页特征
+    ///
+    /// 页面文本较长时多半是真实站点，只在文本较短时匹配，避免误判提到这些短语的文章。
+    pub fn matches_default_page(title: Option<&str>, text: &str) -> bool {
+        const MAX_PLACEHOLDER_TEXT: usize = 1500;
+
+        let title = title.unwrap_or_default().to_lowercase();
+        let text = if text.chars().count() <= MAX_PLACEHOLDER_TEXT {
+            text.to_lowercase()
+        } else {
+            String::new()
+        };
+
+        DEFAULT_PAGE_SIGNATURES
+            .iter()
+            .any(|signature| title.contains(signature) || text.contains(signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// 地址栏下方为纯色或黑白条纹的截图
+    fn screenshot(striped: bool) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(200, 400, |_, y| {
+            if striped && (y / 20) % 2 == 0 {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        }))
+    }
+
+    #[test]
+    fn blank_and_error_pages_are_recognised() {
+        let content = PageContent::default();
+        let blank = screenshot(false);
+        let page = screenshot(true);
+
+        assert_eq!(
+            PageClassifier::classify(Some(&blank), None, &content),
+            PageClass::Blank
+        );
+        assert_eq!(
+            PageClassifier::classify(Some(&page), None, &content),
+            PageClass::Normal
+        );
+        // 只有地址栏高度的截图没有页面内容
+        let bar_only = DynamicImage::new_rgb8(200, ADDRESS_BAR_HEIGHT);
+        assert!(PageClassifier::is_blank_image(&bar_only));
+
+        // Chrome 错误页优先于空白页判断
+        let error = PageContent {
+            chrome_error: true,
+            ..PageContent::default()
+        };
+        assert_eq!(
+            PageClassifier::classify(Some(&blank), None, &error),
+            PageClass::ErrorPage
+        );
+    }
+
+    #[test]
+    fn default_pages_match_only_short_placeholder_text() {
+        let page = screenshot(true);
+        let content = PageContent {
+            text: "If you see this page, the nginx web server is successfully installed."
+                .to_string(),
+            ..PageContent::default()
+        };
+        assert_eq!(
+            PageClassifier::classify(Some(&page), Some("Welcome to nginx!"), &content),
+            PageClass::DefaultPage
+        );
+        assert!(PageClassifier::matches_default_page(
+            None,
+            "This domain is for sale."
+        ));
+
+        // 长文章中提到这些短语不算默认页
+        let article = format!("coming soon: {}", "lorem ipsum ".repeat(200));
+        assert!(!PageClassifier::matches_default_page(
+            Some("Blog"),
+            &article
+        ));
+    }
+}
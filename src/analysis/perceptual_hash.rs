@@ -0,0 +1,54 @@
+use image::DynamicImage;
+use image::imageops::FilterType;
+
+use crate::models::screenshot_error::ScreenshotError;
+
+/// 截图的感知哈希（dHash）
+///
+/// 将图片缩放为 9x8 灰度图，逐行比较相邻像素的明暗得到 64 位哈希，
+/// 对缩放、压缩和细微的文字差异不敏感，适合归并外观相同的页面。
+pub struct PerceptualHash;
+
+impl PerceptualHash {
+    /// 计算图片数据的哈希
+    pub fn from_bytes(data: &[u8]) -> Result<u64, ScreenshotError> {
+        let image = image::load_from_memory(data)
+            .map_err(|e| ScreenshotError::Other(format!("解析截图失败: {}", e)))?;
+        Ok(Self::from_image(&image))
+    }
+
+    /// 计算已解码图片的哈希
+    pub fn from_image(image: &DynamicImage) -> u64 {
+        let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+        let mut hash = 0u64;
+        for y in 0..8 {
+            for x in 0..8 {
+                let left = small.get_pixel(x, y)[0];
+                let right = small.get_pixel(x + 1, y)[0];
+                hash = (hash << 1) | u64::from(left > right);
+            }
+        }
+        hash
+    }
+
+    /// 计算图片文件的哈希
+    pub fn from_file(path: &str) -> Result<u64, ScreenshotError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// 两个哈希之间的汉明距离
+    pub fn distance(a: u64, b: u64) -> u32 {
+        (a ^ b).count_ones()
+    }
+
+    /// 格式化为16位十六进制字符串
+    pub fn to_hex(hash: u64) -> String {
+        format!("{:016x}", hash)
+    }
+
+    /// 解析十六进制字符串
+    pub fn from_hex(text: &str) -> Option<u64> {
+        u64::from_str_radix(text.trim(), 16).ok()
+    }
+}
@@ -0,0 +1,388 @@
+use log::{info, warn};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::analysis::visual_diff::VisualDiff;
+use crate::file_io::file_operations::{
+    CSV_LOG_FILENAME, FileOperations, JSONL_LOG_FILENAME, ScreenshotRecord,
+};
+use crate::models::screenshot_error::ScreenshotError;
+use crate::models::target::Target;
+use crate::utils::path_utils::PathUtils;
+
+/// 判定页面变化的阈值
+#[derive(Debug, Clone)]
+pub struct DiffThresholds {
+    /// 不同像素占比超过该百分比时视为变化
+    pub pixel_percent: f64,
+    /// 单个像素任一通道的差值超过该值才算不同，用于忽略压缩和抗锯齿噪声
+    pub pixel_tolerance: u8,
+}
+
+impl Default for DiffThresholds {
+    fn default() -> Self {
+        Self {
+            pixel_percent: 1.0,
+            pixel_tolerance: 32,
+        }
+    }
+}
+
+/// 一次运行的日志和截图所在目录
+#[derive(Debug, Clone)]
+pub struct RunLog {
+    pub dir: String,
+    pub records: Vec<ScreenshotRecord>,
+}
+
+impl RunLog {
+    /// 读取运行目录中的日志，也可以直接给出CSV/JSONL日志路径
+    pub fn load(path: &str) -> Result<Self, ScreenshotError> {
+        let (dir, log_path) = if Path::new(path).is_file() {
+            (PathUtils::parent_dir(path), path.to_string())
+        } else {
+            let candidates = [
+                Path::new(path)
+                    .join(CSV_LOG_FILENAME)
+                    .to_string_lossy()
+                    .to_string(),
+                // 旧版本在非 Windows 系统上写在目录旁的日志
+                format!("{}\\{}", path, CSV_LOG_FILENAME),
+                Path::new(path)
+                    .join(JSONL_LOG_FILENAME)
+                    .to_string_lossy()
+                    .to_string(),
+            ];
+            let log_path = candidates
+                .into_iter()
+                .find(|candidate| FileOperations::file_exists(candidate))
+                .ok_or_else(|| ScreenshotError::Other(format!("在 {} 中找不到截图日志", path)))?;
+            (path.to_string(), log_path)
+        };
+
+        let records = FileOperations::latest_records(&FileOperations::read_result_log(&log_path)?);
+        Ok(Self { dir, records })
+    }
+
+    /// 截图文件的实际位置，运行目录被移动过时按路径末尾的子目录和文件名在目录中查找
+    fn screenshot_path(&self, record: &ScreenshotRecord) -> Option<String> {
+        let path = record.screenshot_path.as_deref()?;
+        if Path::new(path).is_file() {
+            return Some(path.to_string());
+        }
+
+        let components: Vec<&str> = path.split(['/', '\\']).collect();
+        (0..components.len())
+            .map(|start| {
+                let mut moved = PathBuf::from(&self.dir);
+                moved.extend(&components[start..]);
+                moved
+            })
+            .find(|moved| moved.is_file())
+            .map(|moved| moved.to_string_lossy().to_string())
+    }
+}
+
+/// 两次运行中都出现且发生变化的目标
+#[derive(Debug, Clone)]
+pub struct ChangedTarget {
+    pub target: String,
+    pub old: ScreenshotRecord,
+    pub new: ScreenshotRecord,
+    /// 变化原因
+    pub reasons: Vec<String>,
+    /// 不同像素占比（0.0-1.0），两次都有截图时才有值
+    pub pixel_ratio: Option<f64>,
+    pub hash_distance: Option<u32>,
+    /// 并排对比图路径
+    pub side_by_side_path: Option<String>,
+    /// 差异高亮图路径
+    pub highlight_path: Option<String>,
+}
+
+/// 两次运行的对比结果
+#[derive(Debug, Clone, Default)]
+pub struct RunDiff {
+    /// 仅在新运行中出现的目标
+    pub added: Vec<ScreenshotRecord>,
+    /// 仅在旧运行中出现的目标
+    pub removed: Vec<ScreenshotRecord>,
+    pub changed: Vec<ChangedTarget>,
+    pub unchanged_count: usize,
+}
+
+impl RunDiff {
+    /// 按目标匹配两次运行，对比截图并把变化页面的对比图写入 `output_dir`
+    pub fn compare(
+        old: &RunLog,
+        new: &RunLog,
+        thresholds: &DiffThresholds,
+        output_dir: &str,
+    ) -> Result<Self, ScreenshotError> {
+        FileOperations::ensure_screenshots_dir(output_dir)?;
+
+        let old_by_target: HashMap<&str, &ScreenshotRecord> = old
+            .records
+            .iter()
+            .map(|record| (record.target.as_str(), record))
+            .collect();
+        let new_targets: HashSet<&str> = new
+            .records
+            .iter()
+            .map(|record| record.target.as_str())
+            .collect();
+
+        let mut diff = RunDiff {
+            removed: old
+                .records
+                .iter()
+                .filter(|record| !new_targets.contains(record.target.as_str()))
+                .cloned()
+                .collect(),
+            ..Default::default()
+        };
+
+        for new_record in &new.records {
+            let Some(old_record) = old_by_target.get(new_record.target.as_str()) else {
+                diff.added.push(new_record.clone());
+                continue;
+            };
+
+            match Self::compare_target(old, new, old_record, new_record, thresholds, output_dir) {
+                Some(changed) => diff.changed.push(changed),
+                None => diff.unchanged_count += 1,
+            }
+        }
+
+        info!(
+            "对比完成: 新增 {}，移除 {}，变化 {}，未变化 {}",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len(),
+            diff.unchanged_count
+        );
+        Ok(diff)
+    }
+
+    /// 对比同一目标的两条记录，没有变化时返回 None
+    fn compare_target(
+        old: &RunLog,
+        new: &RunLog,
+        old_record: &ScreenshotRecord,
+        new_record: &ScreenshotRecord,
+        thresholds: &DiffThresholds,
+        output_dir: &str,
+    ) -> Option<ChangedTarget> {
+        let mut changed = ChangedTarget {
+            target: new_record.target.clone(),
+            old: old_record.clone(),
+            new: new_record.clone(),
+            reasons: Vec::new(),
+            pixel_ratio: None,
+            hash_distance: None,
+            side_by_side_path: None,
+            highlight_path: None,
+        };
+
+        if old_record.success != new_record.success {
+            changed.reasons.push(if new_record.success {
+                "截图由失败变为成功".to_string()
+            } else {
+                "截图由成功变为失败".to_string()
+            });
+        }
+        if old_record.status_code != new_record.status_code {
+            changed.reasons.push(format!(
+                "状态码 {} → {}",
+                Self::display(old_record.status_code),
+                Self::display(new_record.status_code)
+            ));
+        }
+        if old_record.title != new_record.title {
+            changed.reasons.push(format!(
+                "标题 {} → {}",
+                old_record.title.as_deref().unwrap_or("-"),
+                new_record.title.as_deref().unwrap_or("-")
+            ));
+        }
+
+        if let (Some(old_path), Some(new_path)) = (
+            old.screenshot_path(old_record),
+            new.screenshot_path(new_record),
+        ) {
+            match VisualDiff::compare(&old_path, &new_path, thresholds.pixel_tolerance) {
+                Ok(image_diff) => {
+                    changed.pixel_ratio = Some(image_diff.pixel_ratio);
+                    changed.hash_distance = Some(image_diff.hash_distance);
+
+                    if image_diff.pixel_ratio * 100.0 > thresholds.pixel_percent {
+                        changed.reasons.push(format!(
+                            "{:.2}% 像素不同（感知哈希距离 {}）",
+                            image_diff.pixel_ratio * 100.0,
+                            image_diff.hash_distance
+                        ));
+                        Self::write_images(
+                            &mut changed,
+                            &old_path,
+                            &new_path,
+                            &image_diff.highlight,
+                            output_dir,
+                        );
+                    }
+                }
+                Err(e) => warn!("无法对比截图 {}: {}", new_record.target, e),
+            }
+        }
+
+        (!changed.reasons.is_empty()).then_some(changed)
+    }
+
+    /// 写入并排对比图和差异高亮图
+    fn write_images(
+        changed: &mut ChangedTarget,
+        old_path: &str,
+        new_path: &str,
+        highlight: &image::RgbaImage,
+        output_dir: &str,
+    ) {
+        let stem = Target::new(changed.target.clone())
+            .map(|target| target.get_clean_filename())
+            .unwrap_or_else(|| changed.target.clone());
+        let highlight_path = Path::new(output_dir)
+            .join(format!("{}_diff.png", stem))
+            .to_string_lossy()
+            .to_string();
+        let side_by_side_path = Path::new(output_dir)
+            .join(format!("{}_side_by_side.png", stem))
+            .to_string_lossy()
+            .to_string();
+
+        match VisualDiff::save(highlight, &highlight_path) {
+            Ok(()) => changed.highlight_path = Some(highlight_path),
+            Err(e) => warn!("{}", e),
+        }
+        match VisualDiff::side_by_side(old_path, new_path, highlight)
+            .and_then(|image| VisualDiff::save(&image, &side_by_side_path))
+        {
+            Ok(()) => changed.side_by_side_path = Some(side_by_side_path),
+            Err(e) => warn!("{}", e),
+        }
+    }
+
+    fn display(status_code: Option<u16>) -> String {
+        status_code.map_or_else(|| "-".to_string(), |code| code.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::fs;
+
+    fn run_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    /// 写入截图并返回对应的日志记录，`dark_rows` 行以上为黑色
+    fn capture(dir: &str, target: &str, dark_rows: u32, title: &str) -> ScreenshotRecord {
+        let image = RgbaImage::from_fn(20, 20, |_, y| {
+            if y < dark_rows {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        });
+        let path = Path::new(dir)
+            .join(format!("{}.png", target.replace(['/', ':'], "_")))
+            .to_string_lossy()
+            .to_string();
+        image.save(&path).unwrap();
+        serde_json::from_value(serde_json::json!({
+            "timestamp": 1_700_000_000,
+            "target": target,
+            "target_type": "url",
+            "success": true,
+            "attempts": 1,
+            "status_code": 200,
+            "title": title,
+            "screenshot_path": path,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn targets_are_reported_as_added_removed_or_changed() {
+        let old_dir = run_dir("diff-old");
+        let new_dir = run_dir("diff-new");
+        let output_dir = run_dir("diff-output");
+
+        let old = RunLog {
+            records: vec![
+                capture(&old_dir, "http://a.example", 0, "A"),
+                capture(&old_dir, "http://b.example", 0, "B"),
+                capture(&old_dir, "http://removed.example", 0, "R"),
+            ],
+            dir: old_dir,
+        };
+        let mut retitled = capture(&new_dir, "http://b.example", 0, "B v2");
+        retitled.status_code = Some(500);
+        let new = RunLog {
+            records: vec![
+                capture(&new_dir, "http://a.example", 10, "A"),
+                retitled,
+                capture(&new_dir, "http://added.example", 0, "N"),
+            ],
+            dir: new_dir,
+        };
+
+        let diff = RunDiff::compare(&old, &new, &DiffThresholds::default(), &output_dir).unwrap();
+        assert_eq!(diff.added[0].target, "http://added.example");
+        assert_eq!(diff.removed[0].target, "http://removed.example");
+        assert_eq!(diff.unchanged_count, 0);
+
+        let changed: Vec<&str> = diff.changed.iter().map(|c| c.target.as_str()).collect();
+        assert_eq!(changed, ["http://a.example", "http://b.example"]);
+
+        // 画面变化：写出并排对比图和差异高亮图
+        let image_change = &diff.changed[0];
+        assert_eq!(image_change.pixel_ratio, Some(0.5));
+        assert!(Path::new(image_change.side_by_side_path.as_deref().unwrap()).is_file());
+        assert!(Path::new(image_change.highlight_path.as_deref().unwrap()).is_file());
+
+        // 截图相同，只有状态码和标题变化
+        let metadata_change = &diff.changed[1];
+        assert_eq!(metadata_change.pixel_ratio, Some(0.0));
+        assert_eq!(
+            metadata_change.reasons,
+            ["状态码 200 → 500", "标题 B → B v2"]
+        );
+        assert!(metadata_change.side_by_side_path.is_none());
+    }
+
+    #[test]
+    fn screenshots_are_found_after_the_run_directory_moves() {
+        let dir = run_dir("diff-moved");
+        let record = capture(&dir, "http://a.example", 0, "A");
+        let moved = run_dir("diff-moved-to");
+        let file_name = Path::new(record.screenshot_path.as_deref().unwrap())
+            .file_name()
+            .unwrap()
+            .to_owned();
+        fs::rename(
+            record.screenshot_path.as_deref().unwrap(),
+            Path::new(&moved).join(&file_name),
+        )
+        .unwrap();
+
+        let log = RunLog {
+            dir: moved.clone(),
+            records: vec![record.clone()],
+        };
+        let found = log.screenshot_path(&record).unwrap();
+        assert_eq!(Path::new(&found), Path::new(&moved).join(file_name));
+    }
+}
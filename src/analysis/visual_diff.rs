@@ -0,0 +1,175 @@
+use image::{Rgba, RgbaImage, imageops};
+
+use crate::analysis::perceptual_hash::PerceptualHash;
+use crate::models::screenshot_error::ScreenshotError;
+
+/// 两张截图之间的差异
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    /// 不同像素占比（0.0-1.0），尺寸不同时超出重叠区域的部分都算作不同
+    pub pixel_ratio: f64,
+    /// 感知哈希的汉明距离
+    pub hash_distance: u32,
+    /// 标出不同像素的新截图
+    pub highlight: RgbaImage,
+}
+
+/// 截图像素对比
+pub struct VisualDiff;
+
+impl VisualDiff {
+    /// 对比两张截图，任一通道差值超过 `tolerance` 的像素视为不同
+    pub fn compare(
+        old_path: &str,
+        new_path: &str,
+        tolerance: u8,
+    ) -> Result<ImageDiff, ScreenshotError> {
+        let old_data = std::fs::read(old_path)?;
+        let new_data = std::fs::read(new_path)?;
+        let old = Self::decode(&old_data)?;
+        let new = Self::decode(&new_data)?;
+
+        let width = old.width().max(new.width());
+        let height = old.height().max(new.height());
+        let mut highlight = RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255]));
+        imageops::replace(&mut highlight, &new, 0, 0);
+
+        let mut changed: u64 = 0;
+        for y in 0..height {
+            for x in 0..width {
+                let different = match (Self::pixel(&old, x, y), Self::pixel(&new, x, y)) {
+                    (Some(a), Some(b)) => {
+                        a.0.iter()
+                            .zip(b.0.iter())
+                            .any(|(a, b)| a.abs_diff(*b) > tolerance)
+                    }
+                    _ => true,
+                };
+                if different {
+                    changed += 1;
+                    // 不同的像素叠加半透明红色，保留原内容便于辨认
+                    let pixel = highlight.get_pixel_mut(x, y);
+                    pixel.0 = [
+                        ((u16::from(pixel.0[0]) + 255) / 2) as u8,
+                        pixel.0[1] / 2,
+                        pixel.0[2] / 2,
+                        255,
+                    ];
+                }
+            }
+        }
+
+        let total = u64::from(width) * u64::from(height);
+        Ok(ImageDiff {
+            pixel_ratio: if total == 0 {
+                0.0
+            } else {
+                changed as f64 / total as f64
+            },
+            hash_distance: PerceptualHash::distance(
+                PerceptualHash::from_bytes(&old_data)?,
+                PerceptualHash::from_bytes(&new_data)?,
+            ),
+            highlight,
+        })
+    }
+
+    /// 生成 旧截图 | 新截图 | 差异图 的并排对比图
+    pub fn side_by_side(
+        old_path: &str,
+        new_path: &str,
+        highlight: &RgbaImage,
+    ) -> Result<RgbaImage, ScreenshotError> {
+        const GAP: u32 = 8;
+
+        let old = Self::decode(&std::fs::read(old_path)?)?;
+        let new = Self::decode(&std::fs::read(new_path)?)?;
+        let width = old.width() + new.width() + highlight.width() + GAP * 2;
+        let height = old.height().max(new.height()).max(highlight.height());
+
+        let mut canvas = RgbaImage::from_pixel(width, height, Rgba([128, 128, 128, 255]));
+        imageops::replace(&mut canvas, &old, 0, 0);
+        imageops::replace(&mut canvas, &new, i64::from(old.width() + GAP), 0);
+        imageops::replace(
+            &mut canvas,
+            highlight,
+            i64::from(old.width() + new.width() + GAP * 2),
+            0,
+        );
+        Ok(canvas)
+    }
+
+    /// 保存为PNG
+    pub fn save(image: &RgbaImage, path: &str) -> Result<(), ScreenshotError> {
+        image
+            .save(path)
+            .map_err(|e| ScreenshotError::Other(format!("保存对比图失败 {}: {}", path, e)))
+    }
+
+    fn decode(data: &[u8]) -> Result<RgbaImage, ScreenshotError> {
+        image::load_from_memory(data)
+            .map(|image| image.to_rgba8())
+            .map_err(|e| ScreenshotError::Other(format!("解析截图失败: {}", e)))
+    }
+
+    fn pixel(image: &RgbaImage, x: u32, y: u32) -> Option<&Rgba<u8>> {
+        (x < image.width() && y < image.height()).then(|| image.get_pixel(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_png(name: &str, image: &RgbaImage) -> String {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-visual-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name).to_string_lossy().to_string();
+        VisualDiff::save(image, &path).unwrap();
+        path
+    }
+
+    #[test]
+    fn differences_beyond_the_tolerance_are_counted_and_highlighted() {
+        let old = RgbaImage::from_pixel(10, 10, Rgba([200, 200, 200, 255]));
+        let mut new = old.clone();
+        // 左上角 2x5 的像素明显不同，其余像素只有轻微的压缩噪声
+        for (x, y, pixel) in new.enumerate_pixels_mut() {
+            pixel.0 = if x < 2 && y < 5 {
+                [0, 0, 0, 255]
+            } else {
+                [210, 200, 195, 255]
+            };
+        }
+        let old_path = write_png("old.png", &old);
+        let new_path = write_png("new.png", &new);
+
+        let diff = VisualDiff::compare(&old_path, &new_path, 32).unwrap();
+        assert!(
+            (diff.pixel_ratio - 0.1).abs() < 1e-9,
+            "{}",
+            diff.pixel_ratio
+        );
+        assert_eq!(diff.highlight.get_pixel(0, 0).0, [127, 0, 0, 255]);
+        assert_eq!(diff.highlight.get_pixel(5, 5).0, [210, 200, 195, 255]);
+
+        let same = VisualDiff::compare(&old_path, &old_path, 0).unwrap();
+        assert_eq!(same.pixel_ratio, 0.0);
+        assert_eq!(same.hash_distance, 0);
+    }
+
+    #[test]
+    fn size_changes_count_as_different_pixels() {
+        let old = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+        let new = RgbaImage::from_pixel(10, 20, Rgba([255, 255, 255, 255]));
+        let old_path = write_png("short.png", &old);
+        let new_path = write_png("tall.png", &new);
+
+        let diff = VisualDiff::compare(&old_path, &new_path, 0).unwrap();
+        assert_eq!(diff.pixel_ratio, 0.5);
+        assert_eq!((diff.highlight.width(), diff.highlight.height()), (10, 20));
+
+        let canvas = VisualDiff::side_by_side(&old_path, &new_path, &diff.highlight).unwrap();
+        assert_eq!((canvas.width(), canvas.height()), (46, 20));
+    }
+}
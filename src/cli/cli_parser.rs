@@ -1,17 +1,265 @@
-use clap::{Arg, Command};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::path::PathBuf;
 
+use crate::analysis::run_diff::DiffThresholds;
+use crate::config::address_bar::FrameTheme;
 use crate::config::app_config::AppConfig;
+use crate::config::config_file::{ConfigFile, ConfigOptions};
+use crate::file_io::output_naming::DEFAULT_NAMING_TEMPLATE;
+use crate::file_io::sqlite_store::CaptureQuery;
+use crate::models::screenshot_error::ScreenshotError;
+
+/// 命令行解析结果
+#[derive(Debug, Clone)]
+pub enum CliCommand {
+    /// 从目标文件截图
+    Capture {
+        config: AppConfig,
+        file_path: String,
+    },
+    /// 查询结果数据库
+    Query(QueryOptions),
+    /// 根据已有日志重新生成报告
+    Report(ReportOptions),
+    /// 对比两次运行的截图
+    Diff(DiffOptions),
+    /// 按完整性清单校验输出目录
+    Verify(VerifyOptions),
+}
+
+/// `query` 子命令参数
+#[derive(Debug, Clone)]
+pub struct QueryOptions {
+    /// 数据库路径
+    pub db_path: String,
+    /// 过滤条件
+    pub query: CaptureQuery,
+    /// 列出所有运行而不是截图记录
+    pub list_runs: bool,
+    /// 只输出去重后的主机名
+    pub hosts_only: bool,
+}
+
+/// 报告格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Markdown,
+    Text,
+    Html,
+}
+
+/// `report` 子命令参数
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    /// CSV或JSONL日志路径
+    pub input_path: String,
+    pub format: ReportFormat,
+    /// 报告输出路径，未指定时写入日志所在目录
+    pub output_path: Option<String>,
+    /// 相似截图聚类的汉明距离阈值
+    pub cluster_threshold: u32,
+    /// 是否隐藏空白页、错误页和默认页
+    pub hide_noise: bool,
+    /// 是否只列出带内容标签的页面
+    pub only_interesting: bool,
+}
+
+/// `diff` 子命令参数
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// 旧运行的目录或日志路径
+    pub old_path: String,
+    /// 新运行的目录或日志路径
+    pub new_path: String,
+    /// 对比图和报告的输出目录
+    pub output_dir: String,
+    pub thresholds: DiffThresholds,
+}
+
+/// `verify` 子命令参数
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    /// 待校验的输出目录或运行归档（.zip / .tar.gz）
+    pub dir: String,
+    /// 清单路径，未指定时使用目录或归档中的 manifest.json
+    pub manifest_path: Option<String>,
+}
 
 /// 命令行参数解析器
 pub struct CliParser;
 
 impl CliParser {
     /// 解析命令行参数
-    pub fn parse() -> AppConfig {
-        let matches = Command::new("picmyweb2")
+    pub fn parse() -> CliCommand {
+        let matches = Self::build_command().get_matches();
+
+        match matches.subcommand() {
+            Some(("query", query_matches)) => CliCommand::Query(Self::parse_query(query_matches)),
+            Some(("report", report_matches)) => {
+                CliCommand::Report(Self::parse_report(report_matches))
+            }
+            Some(("diff", diff_matches)) => CliCommand::Diff(Self::parse_diff(diff_matches)),
+            Some(("verify", verify_matches)) => {
+                CliCommand::Verify(Self::parse_verify(verify_matches))
+            }
+            _ => match Self::parse_capture(&matches) {
+                Ok(config) => CliCommand::Capture {
+                    config,
+                    file_path: matches.get_one::<String>("file").unwrap().clone(),
+                },
+                Err(e) => Self::build_command()
+                    .error(ErrorKind::InvalidValue, e)
+                    .exit(),
+            },
+        }
+    }
+
+    /// 解析截图参数
+    ///
+    /// 优先级从高到低：命令行参数、`PICMYWEB2_*` 环境变量、配置档 `[profile.<名称>]`、
+    /// 配置文件顶层选项、内置默认值。
+    fn parse_capture(matches: &ArgMatches) -> Result<AppConfig, ScreenshotError> {
+        let config_path = matches
+            .get_one::<String>("config")
+            .map(PathBuf::from)
+            .or_else(ConfigFile::locate);
+        let profile = matches.get_one::<String>("profile").map(String::as_str);
+
+        let file_options = match (&config_path, profile) {
+            (Some(path), _) => ConfigFile::load(path)?.options(profile)?,
+            (None, Some(profile)) => {
+                return Err(ScreenshotError::Other(format!(
+                    "未找到配置文件，无法使用配置档 {}",
+                    profile
+                )));
+            }
+            (None, None) => ConfigOptions::default(),
+        };
+
+        file_options
+            .merge(Self::capture_options(matches))
+            .into_config(profile.unwrap_or("default"))
+    }
+
+    /// 命令行和环境变量中显式设置的截图参数，参数定义中的默认值不计入
+    fn capture_options(matches: &ArgMatches) -> ConfigOptions {
+        ConfigOptions {
+            timeout: Self::explicit(matches, "timeout"),
+            deadline: Self::explicit(matches, "deadline"),
+            // 历史行为：给出 --headless 参数即关闭无头模式
+            headless: Self::explicit::<String>(matches, "headless").map(|_| false),
+            concurrency: Self::explicit::<u64>(matches, "concurrency").map(|v| v as usize),
+            max_attempts: Self::explicit(matches, "max-attempts"),
+            rate: Self::explicit(matches, "rate"),
+            per_host_concurrency: Self::explicit::<u64>(matches, "per-host-concurrency")
+                .map(|v| v as usize),
+            host_delay: Self::explicit(matches, "host-delay"),
+            resume: Self::explicit(matches, "resume"),
+            retry_failed: Self::explicit(matches, "retry-failed"),
+            jsonl: Self::explicit(matches, "jsonl"),
+            output: Self::explicit(matches, "output"),
+            sqlite: Self::explicit(matches, "sqlite"),
+            stdout: Self::explicit(matches, "stdout"),
+            cluster_threshold: Self::explicit(matches, "cluster-threshold"),
+            fingerprint_rules: Self::explicit(matches, "fingerprint-rules"),
+            address_bar: Self::explicit(matches, "address-bar"),
+            frame_theme: Self::explicit(matches, "frame-theme"),
+            frame_template: Self::explicit(matches, "frame-template"),
+            naming: Self::explicit(matches, "naming"),
+            run_subdir: Self::explicit(matches, "run-subdir"),
+            run_id: Self::explicit(matches, "run-id"),
+            archive: Self::explicit(matches, "archive"),
+            thumbnails: Self::explicit(matches, "thumbnails"),
+            thumbnail_width: Self::explicit(matches, "thumbnail-width"),
+            thumbnail_format: Self::explicit(matches, "thumbnail-format"),
+            watermark: Self::explicit(matches, "watermark"),
+            ..ConfigOptions::default()
+        }
+    }
+
+    /// 参数的值，仅在来自命令行或环境变量时返回
+    fn explicit<T: Clone + Send + Sync + 'static>(matches: &ArgMatches, id: &str) -> Option<T> {
+        matches
+            .value_source(id)
+            .filter(|source| *source != ValueSource::DefaultValue)
+            .and_then(|_| matches.get_one::<T>(id).cloned())
+    }
+
+    /// 解析 `query` 子命令参数
+    fn parse_query(matches: &ArgMatches) -> QueryOptions {
+        QueryOptions {
+            db_path: matches.get_one::<String>("db").unwrap().clone(),
+            query: CaptureQuery {
+                run_id: matches.get_one::<i64>("run").copied(),
+                status_code: matches.get_one::<u16>("status").copied(),
+                title: matches.get_one::<String>("title").cloned(),
+                host: matches.get_one::<String>("host").cloned(),
+                technology: matches.get_one::<String>("tech").cloned(),
+                tag: matches.get_one::<String>("tag").cloned(),
+                failed_only: matches.get_flag("failed"),
+            },
+            list_runs: matches.get_flag("runs"),
+            hosts_only: matches.get_flag("hosts"),
+        }
+    }
+
+    /// 解析 `report` 子命令参数
+    fn parse_report(matches: &ArgMatches) -> ReportOptions {
+        let format = match matches.get_one::<String>("format").map(String::as_str) {
+            Some("text") => ReportFormat::Text,
+            Some("html") => ReportFormat::Html,
+            _ => ReportFormat::Markdown,
+        };
+
+        ReportOptions {
+            input_path: matches.get_one::<String>("input").unwrap().clone(),
+            format,
+            output_path: matches.get_one::<String>("output").cloned(),
+            cluster_threshold: *matches.get_one::<u32>("cluster-threshold").unwrap(),
+            hide_noise: matches.get_flag("hide-noise"),
+            only_interesting: matches.get_flag("only-interesting"),
+        }
+    }
+
+    /// 解析 `diff` 子命令参数
+    fn parse_diff(matches: &ArgMatches) -> DiffOptions {
+        DiffOptions {
+            old_path: matches.get_one::<String>("old").unwrap().clone(),
+            new_path: matches.get_one::<String>("new").unwrap().clone(),
+            output_dir: matches.get_one::<String>("output").unwrap().clone(),
+            thresholds: DiffThresholds {
+                pixel_percent: *matches.get_one::<f64>("threshold").unwrap(),
+                pixel_tolerance: *matches.get_one::<u8>("tolerance").unwrap(),
+            },
+        }
+    }
+
+    /// 检查运行ID
+    fn parse_run_id(value: &str) -> Result<String, String> {
+        AppConfig::validate_run_id(value).map(|_| value.to_string())
+    }
+
+    /// 解析 `verify` 子命令参数
+    fn parse_verify(matches: &ArgMatches) -> VerifyOptions {
+        VerifyOptions {
+            dir: matches.get_one::<String>("dir").unwrap().clone(),
+            manifest_path: matches.get_one::<String>("manifest").cloned(),
+        }
+    }
+
+    /// 构建命令行参数定义
+    fn build_command() -> Command {
+        Command::new("picmyweb2")
             .version(env!("CARGO_PKG_VERSION"))
             .about("网页截图工具 - 从文本文件读取URL并截图")
             .author("MrBanana @ 佛子岭日夜加班有限公司")
+            .args_conflicts_with_subcommands(true)
+            .subcommand(Self::build_query_command())
+            .subcommand(Self::build_report_command())
+            .subcommand(Self::build_diff_command())
+            .subcommand(Self::build_verify_command())
             .arg(
                 Arg::new("file")
                     .short('f')
@@ -20,74 +268,541 @@ impl CliParser {
                     .help("包含URL的文本文件路径")
                     .required(true),
             )
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .env("PICMYWEB2_CONFIG")
+                    .value_name("FILE")
+                    .help("TOML或YAML配置文件，未指定时依次查找当前目录的 picmyweb2.toml/.yaml/.yml 和用户配置目录中的 picmyweb2/config.toml"),
+            )
+            .arg(
+                Arg::new("profile")
+                    .long("profile")
+                    .env("PICMYWEB2_PROFILE")
+                    .value_name("NAME")
+                    .help("使用配置文件中 [profile.<NAME>] 的选项，名称同时用于命名模板中的 {profile}"),
+            )
             .arg(
                 Arg::new("timeout")
                     .short('t')
                     .long("timeout")
+                    .env("PICMYWEB2_TIMEOUT")
                     .value_name("SECONDS")
+                    .value_parser(clap::value_parser!(u64))
                     .help("页面加载等待时间（秒）")
                     .default_value("5"),
             )
+            .arg(
+                Arg::new("deadline")
+                    .long("deadline")
+                    .env("PICMYWEB2_DEADLINE")
+                    .value_name("SECONDS")
+                    .value_parser(clap::value_parser!(u64))
+                    .help("单个目标截图的总时限（秒），超时后强制结束浏览器并记录为超时")
+                    .default_value("60"),
+            )
             .arg(
                 Arg::new("concurrency")
                     .short('c')
                     .long("concurrency")
+                    .env("PICMYWEB2_CONCURRENCY")
                     .value_name("THREADS")
+                    .value_parser(clap::value_parser!(u64).range(1..))
                     .help("并发线程数")
                     .default_value("4"),
             )
+            .arg(
+                Arg::new("max-attempts")
+                    .long("max-attempts")
+                    .env("PICMYWEB2_MAX_ATTEMPTS")
+                    .value_name("COUNT")
+                    .value_parser(clap::value_parser!(u32).range(1..))
+                    .help("截图失败时的最大尝试次数（仅对浏览器崩溃、超时、连接重置等可重试错误生效）")
+                    .default_value("3"),
+            )
+            .arg(
+                Arg::new("rate")
+                    .long("rate")
+                    .env("PICMYWEB2_RATE")
+                    .value_name("RPS")
+                    .value_parser(clap::value_parser!(f64))
+                    .help("全局每秒最多发起的截图数（默认不限制）"),
+            )
+            .arg(
+                Arg::new("per-host-concurrency")
+                    .long("per-host-concurrency")
+                    .env("PICMYWEB2_PER_HOST_CONCURRENCY")
+                    .value_name("COUNT")
+                    .value_parser(clap::value_parser!(u64).range(1..))
+                    .help("同一主机（按IP归并）同时进行的截图数上限（默认不限制）"),
+            )
+            .arg(
+                Arg::new("host-delay")
+                    .long("host-delay")
+                    .env("PICMYWEB2_HOST_DELAY")
+                    .value_name("MILLISECONDS")
+                    .value_parser(clap::value_parser!(u64))
+                    .help("同一主机两次截图之间的最小间隔（毫秒）")
+                    .default_value("0"),
+            )
             .arg(
                 Arg::new("headless")
                     .long("headless")
                     .help("是否使用无头模式（默认启用）"),
             )
+            .arg(
+                Arg::new("resume")
+                    .long("resume")
+                    .env("PICMYWEB2_RESUME")
+                    .action(ArgAction::SetTrue)
+                    .help("从输出目录中已有的CSV日志续跑，跳过已记录的目标并追加写入日志"),
+            )
+            .arg(
+                Arg::new("retry-failed")
+                    .long("retry-failed")
+                    .env("PICMYWEB2_RETRY_FAILED")
+                    .action(ArgAction::SetTrue)
+                    .requires("resume")
+                    .help("续跑时重新截图之前失败的目标"),
+            )
+            .arg(
+                Arg::new("jsonl")
+                    .long("jsonl")
+                    .env("PICMYWEB2_JSONL")
+                    .action(ArgAction::SetTrue)
+                    .help("额外写入JSON Lines日志，包含响应头、重定向链和最终URL"),
+            )
             .arg(
                 Arg::new("output")
                     .short('o')
                     .long("output")
+                    .env("PICMYWEB2_OUTPUT")
                     .value_name("DIRECTORY")
                     .help("截图保存目录"),
             )
-            .get_matches();
+            .arg(
+                Arg::new("sqlite")
+                    .long("sqlite")
+                    .env("PICMYWEB2_SQLITE")
+                    .value_name("DB")
+                    .help("将结果写入SQLite数据库，多次运行可共用同一个数据库"),
+            )
+            .arg(
+                Arg::new("stdout")
+                    .long("stdout")
+                    .env("PICMYWEB2_STDOUT")
+                    .action(ArgAction::SetTrue)
+                    .help("将每条结果以JSON Lines格式输出到标准输出"),
+            )
+            .arg(
+                Arg::new("cluster-threshold")
+                    .long("cluster-threshold")
+                    .env("PICMYWEB2_CLUSTER_THRESHOLD")
+                    .value_name("BITS")
+                    .value_parser(clap::value_parser!(u32).range(0..=64))
+                    .default_value("10")
+                    .help("相似截图聚类的感知哈希距离阈值（0-64，越小越严格）"),
+            )
+            .arg(
+                Arg::new("fingerprint-rules")
+                    .long("fingerprint-rules")
+                    .env("PICMYWEB2_FINGERPRINT_RULES")
+                    .value_name("FILE")
+                    .help("额外的技术指纹规则文件（Wappalyzer JSON格式），同名规则覆盖内置规则"),
+            )
+            .arg(
+                Arg::new("address-bar")
+                    .long("address-bar")
+                    .env("PICMYWEB2_ADDRESS_BAR")
+                    .value_name("MODE")
+                    .value_parser(["overlay", "composite"])
+                    .default_value("overlay")
+                    .help("地址栏绘制方式：overlay 注入页面，composite 不改动页面、截图后绘制在图片上方"),
+            )
+            .arg(
+                Arg::new("frame-theme")
+                    .long("frame-theme")
+                    .env("PICMYWEB2_FRAME_THEME")
+                    .value_name("THEME")
+                    .value_parser(FrameTheme::NAMES)
+                    .default_value("safari")
+                    .help("地址栏覆盖层模拟的浏览器外观"),
+            )
+            .arg(
+                Arg::new("frame-template")
+                    .long("frame-template")
+                    .env("PICMYWEB2_FRAME_TEMPLATE")
+                    .value_name("FILE")
+                    .help("自定义地址栏覆盖层的HTML模板，可使用 {{url}}、{{title}}、{{time}}、{{status}}、{{security}} 占位符"),
+            )
+            .arg(
+                Arg::new("naming")
+                    .long("naming")
+                    .env("PICMYWEB2_NAMING")
+                    .value_name("TEMPLATE")
+                    .default_value(DEFAULT_NAMING_TEMPLATE)
+                    .help("截图文件命名模板，/ 表示子目录，可用 {name} {host} {port} {index} {date} {time} {timestamp} {hash} {profile} {run_id} {ext}"),
+            )
+            .arg(
+                Arg::new("run-subdir")
+                    .long("run-subdir")
+                    .env("PICMYWEB2_RUN_SUBDIR")
+                    .action(ArgAction::SetTrue)
+                    .help("把本次运行的截图、日志和报告放在输出目录下以运行ID命名的子目录中"),
+            )
+            .arg(
+                Arg::new("run-id")
+                    .long("run-id")
+                    .env("PICMYWEB2_RUN_ID")
+                    .value_name("ID")
+                    .value_parser(Self::parse_run_id)
+                    .help("指定运行ID，配合 --run-subdir --resume 可续跑之前的运行 [default: 启动时间加随机数]"),
+            )
+            .arg(
+                Arg::new("archive")
+                    .long("archive")
+                    .env("PICMYWEB2_ARCHIVE")
+                    .value_name("FORMAT")
+                    .value_parser(["zip", "tar.gz"])
+                    .conflicts_with("resume")
+                    .help("运行结束后把本次运行的截图、日志、报告和清单打包到输出目录旁的单个文件，归档完成后已打包的文件从目录中移除"),
+            )
+            .arg(
+                Arg::new("thumbnails")
+                    .long("thumbnails")
+                    .env("PICMYWEB2_THUMBNAILS")
+                    .action(ArgAction::SetTrue)
+                    .help("为成功的截图在输出目录的 thumbs/ 下生成缩略图，供报告和图库预览"),
+            )
+            .arg(
+                Arg::new("thumbnail-width")
+                    .long("thumbnail-width")
+                    .env("PICMYWEB2_THUMBNAIL_WIDTH")
+                    .value_name("PIXELS")
+                    .value_parser(clap::value_parser!(u32).range(16..=4096))
+                    .default_value("320")
+                    .help("缩略图宽度（像素），高度按比例计算，过长的整页截图只保留顶部"),
+            )
+            .arg(
+                Arg::new("thumbnail-format")
+                    .long("thumbnail-format")
+                    .env("PICMYWEB2_THUMBNAIL_FORMAT")
+                    .value_name("FORMAT")
+                    .value_parser(["jpeg", "webp"])
+                    .default_value("jpeg")
+                    .help("缩略图格式"),
+            )
+            .arg(
+                Arg::new("watermark")
+                    .long("watermark")
+                    .env("PICMYWEB2_WATERMARK")
+                    .action(ArgAction::SetTrue)
+                    .help("在截图下方添加证据水印（截图时间、主机名、运行ID、目标），并写入PNG文本块"),
+            )
+    }
+
+    /// 构建 `query` 子命令参数定义
+    fn build_query_command() -> Command {
+        Command::new("query")
+            .about("查询SQLite结果数据库")
+            .arg(
+                Arg::new("db")
+                    .long("db")
+                    .value_name("DB")
+                    .help("结果数据库路径")
+                    .required(true),
+            )
+            .arg(
+                Arg::new("runs")
+                    .long("runs")
+                    .action(ArgAction::SetTrue)
+                    .help("列出所有运行"),
+            )
+            .arg(
+                Arg::new("run")
+                    .long("run")
+                    .value_name("RUN_ID")
+                    .value_parser(clap::value_parser!(i64))
+                    .help("只查询指定运行"),
+            )
+            .arg(
+                Arg::new("status")
+                    .long("status")
+                    .value_name("CODE")
+                    .value_parser(clap::value_parser!(u16))
+                    .help("按HTTP状态码过滤，例如 401"),
+            )
+            .arg(
+                Arg::new("title")
+                    .long("title")
+                    .value_name("TEXT")
+                    .help("按页面标题包含的文本过滤"),
+            )
+            .arg(
+                Arg::new("host")
+                    .long("host")
+                    .value_name("HOST")
+                    .help("按主机名过滤"),
+            )
+            .arg(
+                Arg::new("tech")
+                    .long("tech")
+                    .value_name("NAME")
+                    .help("按识别出的技术过滤，例如 WordPress"),
+            )
+            .arg(
+                Arg::new("tag")
+                    .long("tag")
+                    .value_name("TAG")
+                    .value_parser([
+                        "login",
+                        "admin_panel",
+                        "directory_listing",
+                        "dashboard",
+                        "default_credentials",
+                    ])
+                    .help("按页面内容标签过滤"),
+            )
+            .arg(
+                Arg::new("failed")
+                    .long("failed")
+                    .action(ArgAction::SetTrue)
+                    .help("只显示失败的截图"),
+            )
+            .arg(
+                Arg::new("hosts")
+                    .long("hosts")
+                    .action(ArgAction::SetTrue)
+                    .help("只输出去重后的主机名"),
+            )
+    }
+
+    /// 构建 `report` 子命令参数定义
+    fn build_report_command() -> Command {
+        Command::new("report")
+            .about("根据已有的CSV或JSONL日志生成报告，无需重新截图")
+            .arg(
+                Arg::new("input")
+                    .short('i')
+                    .long("input")
+                    .value_name("LOG")
+                    .help("CSV或JSONL日志路径（按扩展名识别）")
+                    .required(true),
+            )
+            .arg(
+                Arg::new("format")
+                    .long("format")
+                    .value_name("FORMAT")
+                    .value_parser(["markdown", "text", "html"])
+                    .default_value("markdown")
+                    .help("报告格式"),
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .value_name("FILE")
+                    .help("报告输出路径，默认写入日志所在目录"),
+            )
+            .arg(
+                Arg::new("hide-noise")
+                    .long("hide-noise")
+                    .action(ArgAction::SetTrue)
+                    .help("不在报告中列出空白页、浏览器错误页和默认页"),
+            )
+            .arg(
+                Arg::new("only-interesting")
+                    .long("only-interesting")
+                    .action(ArgAction::SetTrue)
+                    .help("只列出登录页、管理后台、目录列表、仪表盘和默认密码提示等页面"),
+            )
+            .arg(
+                Arg::new("cluster-threshold")
+                    .long("cluster-threshold")
+                    .value_name("BITS")
+                    .value_parser(clap::value_parser!(u32).range(0..=64))
+                    .default_value("10")
+                    .help("相似截图聚类的感知哈希距离阈值（0-64，越小越严格）"),
+            )
+    }
+
+    /// 构建 `diff` 子命令参数定义
+    fn build_diff_command() -> Command {
+        Command::new("diff")
+            .about("对比两次运行的截图，列出新增、移除和变化的目标")
+            .arg(
+                Arg::new("old")
+                    .long("old")
+                    .value_name("RUN")
+                    .help("旧运行的截图目录或日志路径")
+                    .required(true),
+            )
+            .arg(
+                Arg::new("new")
+                    .long("new")
+                    .value_name("RUN")
+                    .help("新运行的截图目录或日志路径")
+                    .required(true),
+            )
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .value_name("DIRECTORY")
+                    .default_value("diff")
+                    .help("对比图和对比报告的输出目录"),
+            )
+            .arg(
+                Arg::new("threshold")
+                    .long("threshold")
+                    .value_name("PERCENT")
+                    .value_parser(clap::value_parser!(f64))
+                    .default_value("1.0")
+                    .help("不同像素占比超过该百分比时视为页面变化"),
+            )
+            .arg(
+                Arg::new("tolerance")
+                    .long("tolerance")
+                    .value_name("VALUE")
+                    .value_parser(clap::value_parser!(u8))
+                    .default_value("32")
+                    .help("单个像素颜色差值超过该值才算不同（0-255）"),
+            )
+    }
+
+    /// 构建 `verify` 子命令参数定义
+    fn build_verify_command() -> Command {
+        Command::new("verify")
+            .about("按 manifest.json 重新计算哈希，列出被改动或缺失的文件")
+            .arg(
+                Arg::new("dir")
+                    .long("dir")
+                    .value_name("DIRECTORY")
+                    .help("待校验的截图输出目录，或 --archive 生成的 .zip / .tar.gz 归档")
+                    .required(true),
+            )
+            .arg(
+                Arg::new("manifest")
+                    .long("manifest")
+                    .value_name("FILE")
+                    .help("清单文件路径 [default: 目录或归档中的 manifest.json]"),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// 子进程中运行环境变量测试时，用于传递配置文件路径的环境变量
+    const PRECEDENCE_CHILD_CONFIG: &str = "PICMYWEB2_TEST_PRECEDENCE_CONFIG";
 
-        let timeout: u64 = matches
-            .get_one::<String>("timeout")
-            .unwrap()
-            .parse()
-            .unwrap_or(5);
+    /// 在临时目录写入配置文件
+    fn write_config(name: &str, text: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-cli-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, text).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// 不读取 `PICMYWEB2_*` 环境变量的命令定义，结果不受开发或 CI 环境影响
+    fn hermetic_command() -> Command {
+        CliParser::build_command().mut_args(|arg| arg.env(None))
+    }
 
-        let concurrency: usize = matches
-            .get_one::<String>("concurrency")
-            .unwrap()
-            .parse()
-            .unwrap_or(4);
+    fn parse_capture(command: Command, args: &[&str]) -> Result<AppConfig, ScreenshotError> {
+        let matches = command
+            .try_get_matches_from(["picmyweb2", "-f", "urls.txt"].iter().chain(args))
+            .unwrap();
+        CliParser::parse_capture(&matches)
+    }
+
+    #[test]
+    fn argument_defaults_do_not_override_config_file() {
+        let path = write_config("defaults.toml", "concurrency = 2\nmax-attempts = 7\n");
 
-        let headless = !matches.contains_id("headless"); // 默认启用无头模式
+        let config = parse_capture(hermetic_command(), &["--config", &path]).unwrap();
+        assert_eq!(config.concurrency, 2);
+        assert_eq!(config.retry.max_attempts, 7);
 
-        let mut config = AppConfig::default();
-        config.timeout_seconds = timeout;
-        config.concurrency = concurrency;
-        config.headless = headless;
+        let args = ["--config", &path, "--concurrency", "9"];
+        let config = parse_capture(hermetic_command(), &args).unwrap();
+        assert_eq!(config.concurrency, 9);
+    }
 
-        if let Some(output_dir) = matches.get_one::<String>("output") {
-            config.screenshots_dir = output_dir.clone();
+    #[test]
+    fn precedence_is_cli_env_profile_file_default() {
+        let path = write_config(
+            "precedence.toml",
+            r#"
+timeout = 11
+cluster-threshold = 3
+rate = 1.0
+host-delay = 100
+
+[profile.fast]
+cluster-threshold = 4
+rate = 2.0
+host-delay = 200
+"#,
+        );
+
+        // 环境变量只在子进程中设置，不修改本进程的环境
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap());
+        child.args([
+            "--exact",
+            "cli::cli_parser::tests::precedence_in_child_process",
+            "--ignored",
+            "--test-threads=1",
+        ]);
+        for (key, _) in std::env::vars_os() {
+            if key.to_string_lossy().starts_with("PICMYWEB2_") {
+                child.env_remove(key);
+            }
         }
+        let output = child
+            .env(PRECEDENCE_CHILD_CONFIG, &path)
+            .env("PICMYWEB2_RATE", "3.0")
+            .env("PICMYWEB2_HOST_DELAY", "300")
+            .output()
+            .unwrap();
 
-        config
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{}", stdout);
+        assert!(stdout.contains("1 passed"), "{}", stdout);
     }
 
-    /// 获取文件路径参数
-    pub fn get_file_path() -> String {
-        let matches = Command::new("picmyweb2")
-            .arg(
-                Arg::new("file")
-                    .short('f')
-                    .long("file")
-                    .value_name("FILE")
-                    .required(true),
-            )
-            .get_matches();
+    #[test]
+    #[ignore = "由 precedence_is_cli_env_profile_file_default 在子进程中运行"]
+    fn precedence_in_child_process() {
+        let Ok(path) = std::env::var(PRECEDENCE_CHILD_CONFIG) else {
+            return;
+        };
+        let args = [
+            "--config",
+            &path,
+            "--profile",
+            "fast",
+            "--host-delay",
+            "400",
+        ];
+        let config = parse_capture(CliParser::build_command(), &args).unwrap();
+
+        assert_eq!(config.rate_limit.per_host_delay_ms, 400);
+        assert_eq!(config.rate_limit.requests_per_second, Some(3.0));
+        assert_eq!(config.cluster_threshold, 4);
+        assert_eq!(config.timeout_seconds, 11);
+        assert_eq!(config.capture_deadline_seconds, 60);
+        assert_eq!(config.profile, "fast");
+    }
 
-        matches.get_one::<String>("file").unwrap().clone()
+    #[test]
+    fn missing_profile_is_an_error() {
+        let path = write_config("no-profile.toml", "timeout = 11\n");
+        let args = ["--config", &path, "--profile", "fast"];
+        let error = parse_capture(hermetic_command(), &args).unwrap_err();
+        assert!(error.to_string().contains("fast"), "{}", error);
     }
 }
@@ -0,0 +1,48 @@
+/// 截图中地址栏的绘制方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressBarMode {
+    /// 截图前向页面注入地址栏覆盖层
+    #[default]
+    Overlay,
+    /// 不改动页面，截图后在图片上方绘制地址栏、状态码和截图时间
+    Composite,
+}
+
+/// 注入地址栏覆盖层时模拟的浏览器外观
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameTheme {
+    /// 移动版 Safari
+    #[default]
+    MobileSafari,
+    /// 桌面版 Chrome 浅色主题
+    ChromeLight,
+    /// 桌面版 Chrome 深色主题
+    ChromeDark,
+    /// 桌面版 Firefox
+    Firefox,
+    /// 只显示 URL 和截图时间的简洁样式
+    Minimal,
+}
+
+impl FrameTheme {
+    /// 命令行中使用的主题名称
+    pub const NAMES: [&'static str; 5] = [
+        "safari",
+        "chrome-light",
+        "chrome-dark",
+        "firefox",
+        "minimal",
+    ];
+
+    /// 根据命令行中的名称解析主题
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "safari" => Some(FrameTheme::MobileSafari),
+            "chrome-light" => Some(FrameTheme::ChromeLight),
+            "chrome-dark" => Some(FrameTheme::ChromeDark),
+            "firefox" => Some(FrameTheme::Firefox),
+            "minimal" => Some(FrameTheme::Minimal),
+            _ => None,
+        }
+    }
+}
@@ -1,10 +1,23 @@
+use chrono::Utc;
 use std::time::Duration;
 
+use crate::analysis::clustering::DEFAULT_CLUSTER_THRESHOLD;
+use crate::config::address_bar::{AddressBarMode, FrameTheme};
+use crate::config::archive_format::ArchiveFormat;
+use crate::config::rate_limit::RateLimitConfig;
+use crate::config::retry_policy::RetryPolicy;
+use crate::config::thumbnail::ThumbnailConfig;
+use crate::file_io::output_naming::DEFAULT_NAMING_TEMPLATE;
+
 /// 应用程序配置
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     /// 默认等待超时时间（秒）
     pub timeout_seconds: u64,
+    /// 单个目标截图的总时限（秒），超时后强制结束浏览器
+    pub capture_deadline_seconds: u64,
+    /// 收到停止信号后，等待进行中截图完成的宽限期（秒）
+    pub shutdown_grace_seconds: u64,
     /// 是否使用无头模式
     pub headless: bool,
     /// 浏览器窗口尺寸
@@ -15,17 +28,73 @@ pub struct AppConfig {
     pub user_agent: String,
     /// 并发线程数
     pub concurrency: usize,
+    /// 截图失败时的重试策略
+    pub retry: RetryPolicy,
+    /// 限速配置
+    pub rate_limit: RateLimitConfig,
+    /// 是否从已有的CSV日志续跑
+    pub resume: bool,
+    /// 续跑时是否重新截图之前失败的目标
+    pub retry_failed: bool,
+    /// 是否额外写入JSON Lines格式的结果日志
+    pub jsonl_log: bool,
+    /// SQLite结果数据库路径
+    pub sqlite_path: Option<String>,
+    /// 是否把结果以JSON Lines格式输出到标准输出
+    pub stdout_log: bool,
+    /// 相似截图聚类的汉明距离阈值
+    pub cluster_threshold: u32,
+    /// 额外的技术指纹规则文件
+    pub fingerprint_rules: Option<String>,
+    /// 地址栏的绘制方式
+    pub address_bar: AddressBarMode,
+    /// 地址栏覆盖层模拟的浏览器外观
+    pub frame_theme: FrameTheme,
+    /// 自定义地址栏覆盖层的HTML模板文件，设置后忽略 `frame_theme`
+    pub frame_template: Option<String>,
+    /// 是否在截图下方添加证据水印并写入PNG文本块
+    pub watermark: bool,
+    /// 本次运行的ID，写入证据水印，也可用于输出子目录和文件名
+    pub run_id: String,
+    /// 配置档名称，用于命名模板中的 `{profile}`
+    pub profile: String,
+    /// 截图文件的命名模板，`/` 表示子目录
+    pub naming_template: String,
+    /// 运行结束后把输出打包为单个文件的格式
+    pub archive: Option<ArchiveFormat>,
+    /// 缩略图配置，`None` 表示不生成缩略图
+    pub thumbnail: Option<ThumbnailConfig>,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             timeout_seconds: 5,
+            capture_deadline_seconds: 60,
+            shutdown_grace_seconds: 10,
             headless: true,
             window_size: (414, 896), // iPhone XR 尺寸
             screenshots_dir: Self::get_default_screenshots_dir(),
             user_agent: "User-Agent,Mozilla/5.0 (iPhone; U; CPU iPhone OS 4_3_3 like Mac OS X; en-us) AppleWebKit/533.17.9 (KHTML, like Gecko) Version/5.0.2 Mobile/8J2 Safari/6533.18.5".to_string(),
             concurrency: 4, // 默认4个并发线程
+            retry: RetryPolicy::default(),
+            rate_limit: RateLimitConfig::default(),
+            resume: false,
+            retry_failed: false,
+            jsonl_log: false,
+            sqlite_path: None,
+            stdout_log: false,
+            cluster_threshold: DEFAULT_CLUSTER_THRESHOLD,
+            fingerprint_rules: None,
+            address_bar: AddressBarMode::default(),
+            frame_theme: FrameTheme::default(),
+            frame_template: None,
+            watermark: false,
+            run_id: Self::generate_run_id(),
+            profile: "default".to_string(),
+            naming_template: DEFAULT_NAMING_TEMPLATE.to_string(),
+            archive: None,
+            thumbnail: None,
         }
     }
 }
@@ -40,8 +109,41 @@ impl AppConfig {
         }
     }
 
+    /// 生成运行ID：启动时间（UTC）加4位随机十六进制数
+    pub fn generate_run_id() -> String {
+        format!(
+            "{}-{:04x}",
+            Utc::now().format("%Y%m%dT%H%M%SZ"),
+            rand::random::<u16>()
+        )
+    }
+
+    /// 运行ID会用作目录名，只允许字母、数字、`-`、`_` 和 `.`
+    pub fn validate_run_id(value: &str) -> Result<(), String> {
+        let valid = !value.is_empty()
+            && !value.starts_with('.')
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if valid {
+            Ok(())
+        } else {
+            Err("运行ID只能包含字母、数字、-、_ 和 .，且不能以 . 开头".to_string())
+        }
+    }
+
     /// 获取等待超时时间
     pub fn get_timeout_duration(&self) -> Duration {
         Duration::from_secs(self.timeout_seconds)
     }
+
+    /// 获取单个目标截图的总时限
+    pub fn get_capture_deadline(&self) -> Duration {
+        Duration::from_secs(self.capture_deadline_seconds)
+    }
+
+    /// 获取停止时的宽限期
+    pub fn get_shutdown_grace(&self) -> Duration {
+        Duration::from_secs(self.shutdown_grace_seconds)
+    }
 }
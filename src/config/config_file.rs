@@ -0,0 +1,531 @@
+use log::info;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::address_bar::{AddressBarMode, FrameTheme};
+use crate::config::app_config::AppConfig;
+use crate::config::archive_format::ArchiveFormat;
+use crate::config::rate_limit::{MAX_HOST_DELAY_MS, MIN_REQUESTS_PER_SECOND};
+use crate::config::thumbnail::{ThumbnailConfig, ThumbnailFormat};
+use crate::models::screenshot_error::ScreenshotError;
+
+/// 当前目录中依次查找的配置文件名
+const LOCAL_CONFIG_NAMES: [&str; 3] = ["picmyweb2.toml", "picmyweb2.yaml", "picmyweb2.yml"];
+
+/// 用户配置目录中依次查找的配置文件名
+const USER_CONFIG_NAMES: [&str; 3] = ["config.toml", "config.yaml", "config.yml"];
+
+/// 配置文件中定义配置档的键
+const PROFILE_KEY: &str = "profile";
+
+/// 截图参数的一组取值，未设置的项不覆盖其他来源的值
+///
+/// 键名与命令行参数的长名称相同，例如 `per-host-concurrency`。
+/// 只能在配置文件中设置的项：`shutdown-grace`、`window-size`、`user-agent`、
+/// `retry-base-delay`、`retry-max-delay`、`retry-jitter`。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ConfigOptions {
+    pub timeout: Option<u64>,
+    pub deadline: Option<u64>,
+    pub shutdown_grace: Option<u64>,
+    pub headless: Option<bool>,
+    pub window_size: Option<(u32, u32)>,
+    pub user_agent: Option<String>,
+    pub concurrency: Option<usize>,
+    pub max_attempts: Option<u32>,
+    pub retry_base_delay: Option<u64>,
+    pub retry_max_delay: Option<u64>,
+    pub retry_jitter: Option<bool>,
+    pub rate: Option<f64>,
+    pub per_host_concurrency: Option<usize>,
+    pub host_delay: Option<u64>,
+    pub resume: Option<bool>,
+    pub retry_failed: Option<bool>,
+    pub jsonl: Option<bool>,
+    pub output: Option<String>,
+    pub sqlite: Option<String>,
+    pub stdout: Option<bool>,
+    pub cluster_threshold: Option<u32>,
+    pub fingerprint_rules: Option<String>,
+    pub address_bar: Option<String>,
+    pub frame_theme: Option<String>,
+    pub frame_template: Option<String>,
+    pub naming: Option<String>,
+    pub run_subdir: Option<bool>,
+    pub run_id: Option<String>,
+    pub archive: Option<String>,
+    pub thumbnails: Option<bool>,
+    pub thumbnail_width: Option<u32>,
+    pub thumbnail_format: Option<String>,
+    pub watermark: Option<bool>,
+}
+
+impl ConfigOptions {
+    /// 以 `overrides` 中已设置的项覆盖当前取值
+    pub fn merge(self, overrides: Self) -> Self {
+        Self {
+            timeout: overrides.timeout.or(self.timeout),
+            deadline: overrides.deadline.or(self.deadline),
+            shutdown_grace: overrides.shutdown_grace.or(self.shutdown_grace),
+            headless: overrides.headless.or(self.headless),
+            window_size: overrides.window_size.or(self.window_size),
+            user_agent: overrides.user_agent.or(self.user_agent),
+            concurrency: overrides.concurrency.or(self.concurrency),
+            max_attempts: overrides.max_attempts.or(self.max_attempts),
+            retry_base_delay: overrides.retry_base_delay.or(self.retry_base_delay),
+            retry_max_delay: overrides.retry_max_delay.or(self.retry_max_delay),
+            retry_jitter: overrides.retry_jitter.or(self.retry_jitter),
+            rate: overrides.rate.or(self.rate),
+            per_host_concurrency: overrides.per_host_concurrency.or(self.per_host_concurrency),
+            host_delay: overrides.host_delay.or(self.host_delay),
+            resume: overrides.resume.or(self.resume),
+            retry_failed: overrides.retry_failed.or(self.retry_failed),
+            jsonl: overrides.jsonl.or(self.jsonl),
+            output: overrides.output.or(self.output),
+            sqlite: overrides.sqlite.or(self.sqlite),
+            stdout: overrides.stdout.or(self.stdout),
+            cluster_threshold: overrides.cluster_threshold.or(self.cluster_threshold),
+            fingerprint_rules: overrides.fingerprint_rules.or(self.fingerprint_rules),
+            address_bar: overrides.address_bar.or(self.address_bar),
+            frame_theme: overrides.frame_theme.or(self.frame_theme),
+            frame_template: overrides.frame_template.or(self.frame_template),
+            naming: overrides.naming.or(self.naming),
+            run_subdir: overrides.run_subdir.or(self.run_subdir),
+            run_id: overrides.run_id.or(self.run_id),
+            archive: overrides.archive.or(self.archive),
+            thumbnails: overrides.thumbnails.or(self.thumbnails),
+            thumbnail_width: overrides.thumbnail_width.or(self.thumbnail_width),
+            thumbnail_format: overrides.thumbnail_format.or(self.thumbnail_format),
+            watermark: overrides.watermark.or(self.watermark),
+        }
+    }
+
+    /// 在默认配置上应用已设置的项，`profile` 写入 `AppConfig.profile`
+    pub fn into_config(self, profile: &str) -> Result<AppConfig, ScreenshotError> {
+        let invalid = |key: &str, reason: String| {
+            ScreenshotError::Other(format!("配置项 {} 无效: {}", key, reason))
+        };
+
+        let mut config = AppConfig {
+            profile: profile.to_string(),
+            ..AppConfig::default()
+        };
+
+        if let Some(timeout) = self.timeout {
+            config.timeout_seconds = timeout;
+        }
+        if let Some(deadline) = self.deadline {
+            config.capture_deadline_seconds = deadline;
+        }
+        if let Some(grace) = self.shutdown_grace {
+            config.shutdown_grace_seconds = grace;
+        }
+        if let Some(headless) = self.headless {
+            config.headless = headless;
+        }
+        if let Some((width, height)) = self.window_size {
+            if width == 0 || height == 0 {
+                return Err(invalid("window-size", "宽和高必须大于0".to_string()));
+            }
+            config.window_size = (width, height);
+        }
+        if let Some(user_agent) = self.user_agent {
+            config.user_agent = user_agent;
+        }
+        if let Some(concurrency) = self.concurrency {
+            if concurrency == 0 {
+                return Err(invalid("concurrency", "必须大于0".to_string()));
+            }
+            config.concurrency = concurrency;
+        }
+
+        if let Some(max_attempts) = self.max_attempts {
+            config.retry.max_attempts = max_attempts.max(1);
+        }
+        if let Some(delay) = self.retry_base_delay {
+            config.retry.base_delay_ms = delay;
+        }
+        if let Some(delay) = self.retry_max_delay {
+            config.retry.max_delay_ms = delay;
+        }
+        if let Some(jitter) = self.retry_jitter {
+            config.retry.jitter = jitter;
+        }
+
+        if let Some(rate) = self.rate {
+            if !rate.is_finite() || rate < 0.0 || (rate > 0.0 && rate < MIN_REQUESTS_PER_SECOND) {
+                return Err(invalid(
+                    "rate",
+                    format!(
+                        "必须为 0（不限制）或不小于 {:e} 的有限数（每天一次）",
+                        MIN_REQUESTS_PER_SECOND
+                    ),
+                ));
+            }
+            config.rate_limit.requests_per_second = Some(rate);
+        }
+        if let Some(per_host) = self.per_host_concurrency {
+            config.rate_limit.per_host_concurrency = Some(per_host);
+        }
+        if let Some(delay) = self.host_delay {
+            if delay > MAX_HOST_DELAY_MS {
+                return Err(invalid(
+                    "host-delay",
+                    format!("不能超过 {} 毫秒（一天）", MAX_HOST_DELAY_MS),
+                ));
+            }
+            config.rate_limit.per_host_delay_ms = delay;
+        }
+
+        config.resume = self.resume.unwrap_or(config.resume);
+        config.retry_failed = self.retry_failed.unwrap_or(config.retry_failed);
+        config.jsonl_log = self.jsonl.unwrap_or(config.jsonl_log);
+        config.stdout_log = self.stdout.unwrap_or(config.stdout_log);
+        config.watermark = self.watermark.unwrap_or(config.watermark);
+        if self.sqlite.is_some() {
+            config.sqlite_path = self.sqlite;
+        }
+        if self.fingerprint_rules.is_some() {
+            config.fingerprint_rules = self.fingerprint_rules;
+        }
+        if self.frame_template.is_some() {
+            config.frame_template = self.frame_template;
+        }
+
+        if let Some(threshold) = self.cluster_threshold {
+            if threshold > 64 {
+                return Err(invalid("cluster-threshold", "取值范围为 0-64".to_string()));
+            }
+            config.cluster_threshold = threshold;
+        }
+        if let Some(mode) = self.address_bar {
+            config.address_bar = match mode.as_str() {
+                "overlay" => AddressBarMode::Overlay,
+                "composite" => AddressBarMode::Composite,
+                _ => {
+                    return Err(invalid(
+                        "address-bar",
+                        "可选 overlay、composite".to_string(),
+                    ));
+                }
+            };
+        }
+        if let Some(theme) = self.frame_theme {
+            config.frame_theme = FrameTheme::parse(&theme).ok_or_else(|| {
+                invalid(
+                    "frame-theme",
+                    format!("可选 {}", FrameTheme::NAMES.join("、")),
+                )
+            })?;
+        }
+        if let Some(naming) = self.naming {
+            config.naming_template = naming;
+        }
+        if let Some(archive) = self.archive {
+            config.archive = Some(
+                ArchiveFormat::parse(&archive)
+                    .ok_or_else(|| invalid("archive", "可选 zip、tar.gz".to_string()))?,
+            );
+        }
+        if config.archive.is_some() && config.resume {
+            return Err(ScreenshotError::Other(
+                "archive 不能与 resume 同时使用".to_string(),
+            ));
+        }
+
+        if self.thumbnails.unwrap_or(false) {
+            let mut thumbnail = ThumbnailConfig::default();
+            if let Some(width) = self.thumbnail_width {
+                if !(16..=4096).contains(&width) {
+                    return Err(invalid("thumbnail-width", "取值范围为 16-4096".to_string()));
+                }
+                thumbnail.width = width;
+            }
+            if let Some(format) = self.thumbnail_format {
+                thumbnail.format = ThumbnailFormat::parse(&format)
+                    .ok_or_else(|| invalid("thumbnail-format", "可选 jpeg、webp".to_string()))?;
+            }
+            config.thumbnail = Some(thumbnail);
+        }
+
+        // 输出目录和运行ID确定后再拼接运行子目录
+        if let Some(output) = self.output {
+            config.screenshots_dir = output;
+        }
+        if let Some(run_id) = self.run_id {
+            AppConfig::validate_run_id(&run_id).map_err(|reason| invalid("run-id", reason))?;
+            config.run_id = run_id;
+        }
+        if self.run_subdir.unwrap_or(false) {
+            config.screenshots_dir = Path::new(&config.screenshots_dir)
+                .join(&config.run_id)
+                .to_string_lossy()
+                .to_string();
+        }
+
+        Ok(config)
+    }
+}
+
+/// TOML 或 YAML 格式的配置文件
+///
+/// 顶层设置所有运行共用的选项，`[profile.<名称>]` 中的选项在使用该配置档时覆盖顶层选项：
+///
+/// ```toml
+/// concurrency = 8
+/// output = "shots"
+///
+/// [profile.internal]
+/// rate = 2.0
+/// naming = "{host}/{date}_{profile}.{ext}"
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    /// 配置文件路径
+    pub path: String,
+    /// 顶层选项
+    pub options: ConfigOptions,
+    /// 按名称排列的配置档
+    pub profiles: BTreeMap<String, ConfigOptions>,
+}
+
+impl ConfigFile {
+    /// 按默认搜索路径查找配置文件
+    ///
+    /// 依次查找当前目录的 `picmyweb2.toml` / `picmyweb2.yaml` / `picmyweb2.yml`，
+    /// 以及用户配置目录（Windows 为 `%APPDATA%\picmyweb2`，其他系统为
+    /// `$XDG_CONFIG_HOME/picmyweb2` 或 `~/.config/picmyweb2`）中的 `config.toml` / `config.yaml` / `config.yml`。
+    pub fn locate() -> Option<PathBuf> {
+        let local = LOCAL_CONFIG_NAMES.iter().map(PathBuf::from);
+        let user = Self::user_config_dir()
+            .into_iter()
+            .flat_map(|dir| USER_CONFIG_NAMES.iter().map(move |name| dir.join(name)));
+        local.chain(user).find(|path| path.is_file())
+    }
+
+    /// 读取配置文件，扩展名为 `.yaml` / `.yml` 时按 YAML 解析，否则按 TOML 解析
+    pub fn load(path: &Path) -> Result<Self, ScreenshotError> {
+        let display = path.to_string_lossy().to_string();
+        let text = fs::read_to_string(path)
+            .map_err(|e| ScreenshotError::Other(format!("读取配置文件失败 {}: {}", display, e)))?;
+        let invalid = |reason: String| {
+            ScreenshotError::Other(format!("配置文件格式错误 {}: {}", display, reason))
+        };
+
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+        // 先解析为通用的 JSON 值，两种格式共用同一套选项定义和未知键检查
+        let value: serde_json::Value = if is_yaml {
+            serde_yaml::from_str(&text).map_err(|e| invalid(e.to_string()))?
+        } else {
+            toml::from_str(&text).map_err(|e| invalid(e.to_string()))?
+        };
+
+        let mut table = match value {
+            serde_json::Value::Object(table) => table,
+            serde_json::Value::Null => serde_json::Map::new(),
+            _ => return Err(invalid("顶层必须是键值表".to_string())),
+        };
+        let profiles = match table.remove(PROFILE_KEY) {
+            Some(profiles) => serde_json::from_value(profiles)
+                .map_err(|e| invalid(format!("{}: {}", PROFILE_KEY, e)))?,
+            None => BTreeMap::new(),
+        };
+        let options = serde_json::from_value(serde_json::Value::Object(table))
+            .map_err(|e| invalid(e.to_string()))?;
+
+        info!("读取配置文件: {}", display);
+        Ok(Self {
+            path: display,
+            options,
+            profiles,
+        })
+    }
+
+    /// 顶层选项叠加指定配置档后的取值
+    pub fn options(&self, profile: Option<&str>) -> Result<ConfigOptions, ScreenshotError> {
+        let Some(name) = profile else {
+            return Ok(self.options.clone());
+        };
+        let overrides = self.profiles.get(name).ok_or_else(|| {
+            let available = self.profiles.keys().cloned().collect::<Vec<_>>();
+            ScreenshotError::Other(format!(
+                "配置文件 {} 中没有配置档 {}（已定义: {}）",
+                self.path,
+                name,
+                if available.is_empty() {
+                    "无".to_string()
+                } else {
+                    available.join(", ")
+                }
+            ))
+        })?;
+        Ok(self.options.clone().merge(overrides.clone()))
+    }
+
+    /// 用户配置目录下的 `picmyweb2` 子目录
+    fn user_config_dir() -> Option<PathBuf> {
+        let base = if cfg!(windows) {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        };
+        base.map(|dir| dir.join("picmyweb2"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在临时目录写入配置文件
+    fn write_config(name: &str, text: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn merge_prefers_overrides_and_keeps_unset_values() {
+        let base = ConfigOptions {
+            timeout: Some(10),
+            concurrency: Some(2),
+            naming: Some("{host}.{ext}".to_string()),
+            ..ConfigOptions::default()
+        };
+        let overrides = ConfigOptions {
+            concurrency: Some(8),
+            rate: Some(1.5),
+            ..ConfigOptions::default()
+        };
+
+        let merged = base.merge(overrides);
+        assert_eq!(merged.timeout, Some(10));
+        assert_eq!(merged.concurrency, Some(8));
+        assert_eq!(merged.rate, Some(1.5));
+        assert_eq!(merged.naming.as_deref(), Some("{host}.{ext}"));
+        assert_eq!(merged.host_delay, None);
+    }
+
+    #[test]
+    fn unset_options_fall_back_to_defaults() {
+        let config = ConfigOptions {
+            timeout: Some(30),
+            ..ConfigOptions::default()
+        }
+        .into_config("default")
+        .unwrap();
+        let defaults = AppConfig::default();
+
+        assert_eq!(config.timeout_seconds, 30);
+        assert_eq!(config.concurrency, defaults.concurrency);
+        assert_eq!(config.retry.max_attempts, defaults.retry.max_attempts);
+        assert_eq!(config.profile, "default");
+    }
+
+    #[test]
+    fn profile_overrides_top_level_options() {
+        let path = write_config(
+            "profiles.toml",
+            r#"
+timeout = 11
+concurrency = 2
+
+[profile.fast]
+concurrency = 16
+rate = 4.0
+"#,
+        );
+        let file = ConfigFile::load(&path).unwrap();
+
+        let top = file.options(None).unwrap();
+        assert_eq!(top.concurrency, Some(2));
+        assert_eq!(top.rate, None);
+
+        let fast = file.options(Some("fast")).unwrap();
+        assert_eq!(fast.timeout, Some(11));
+        assert_eq!(fast.concurrency, Some(16));
+        assert_eq!(fast.rate, Some(4.0));
+
+        let error = file.options(Some("slow")).unwrap_err();
+        assert!(error.to_string().contains("fast"), "{}", error);
+    }
+
+    #[test]
+    fn yaml_uses_the_same_options() {
+        let path = write_config(
+            "profiles.yaml",
+            "timeout: 12\nprofile:\n  ci:\n    headless: false\n",
+        );
+        let file = ConfigFile::load(&path).unwrap();
+
+        let ci = file.options(Some("ci")).unwrap();
+        assert_eq!(ci.timeout, Some(12));
+        assert_eq!(ci.headless, Some(false));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let path = write_config("unknown.toml", "timout = 5\n");
+        let error = ConfigFile::load(&path).unwrap_err();
+        assert!(error.to_string().contains("timout"), "{}", error);
+
+        let path = write_config("unknown-profile.toml", "[profile.ci]\nheadles = false\n");
+        let error = ConfigFile::load(&path).unwrap_err();
+        assert!(error.to_string().contains("headles"), "{}", error);
+    }
+
+    #[test]
+    fn archive_cannot_be_combined_with_resume() {
+        // 配置文件开启归档、命令行续跑，同样会被拒绝
+        let file = ConfigOptions {
+            archive: Some("zip".to_string()),
+            ..ConfigOptions::default()
+        };
+        let cli = ConfigOptions {
+            resume: Some(true),
+            ..ConfigOptions::default()
+        };
+
+        let error = file.clone().merge(cli).into_config("default").unwrap_err();
+        assert!(error.to_string().contains("resume"), "{}", error);
+        assert!(file.into_config("default").unwrap().archive.is_some());
+    }
+
+    #[test]
+    fn rate_limits_outside_the_supported_range_are_rejected() {
+        for rate in [1e-300, 1e-320, -1.0, f64::INFINITY] {
+            let options = ConfigOptions {
+                rate: Some(rate),
+                ..ConfigOptions::default()
+            };
+            let error = options.into_config("default").unwrap_err();
+            assert!(error.to_string().contains("rate"), "{}", error);
+        }
+        let options = ConfigOptions {
+            host_delay: Some(u64::MAX),
+            ..ConfigOptions::default()
+        };
+        assert!(options.into_config("default").is_err());
+
+        let config = ConfigOptions {
+            rate: Some(MIN_REQUESTS_PER_SECOND),
+            ..ConfigOptions::default()
+        }
+        .into_config("default")
+        .unwrap();
+        assert_eq!(
+            config.rate_limit.global_interval(),
+            Some(std::time::Duration::from_secs(86_400))
+        );
+    }
+}
@@ -2,4 +2,10 @@
 //!
 //! 包含应用程序的配置管理和默认值定义
 
+pub mod address_bar;
 pub mod app_config;
+pub mod archive_format;
+pub mod config_file;
+pub mod rate_limit;
+pub mod retry_policy;
+pub mod thumbnail;
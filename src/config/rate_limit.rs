@@ -0,0 +1,32 @@
+use std::time::Duration;
+
+/// 全局限速的最低速率：每天一次截图，更小的正数会让间隔超出 `Duration` 的范围
+pub const MIN_REQUESTS_PER_SECOND: f64 = 1.0 / 86_400.0;
+
+/// 同一主机两次截图之间的最大间隔（毫秒）：一天
+pub const MAX_HOST_DELAY_MS: u64 = 86_400_000;
+
+/// 限速配置
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+    /// 全局每秒最多发起的截图数，`None` 表示不限制
+    pub requests_per_second: Option<f64>,
+    /// 同一主机（按解析出的IP归并）同时进行的截图数上限，`None` 表示不限制
+    pub per_host_concurrency: Option<usize>,
+    /// 同一主机两次截图之间的最小间隔（毫秒）
+    pub per_host_delay_ms: u64,
+}
+
+impl RateLimitConfig {
+    /// 全局两次截图之间的最小间隔，速率低于 [`MIN_REQUESTS_PER_SECOND`] 时按最低速率计算
+    pub fn global_interval(&self) -> Option<Duration> {
+        self.requests_per_second
+            .filter(|rps| *rps > 0.0)
+            .map(|rps| Duration::from_secs_f64(1.0 / rps.max(MIN_REQUESTS_PER_SECOND)))
+    }
+
+    /// 同一主机两次截图之间的最小间隔，最长为 [`MAX_HOST_DELAY_MS`]
+    pub fn per_host_delay(&self) -> Duration {
+        Duration::from_millis(self.per_host_delay_ms.min(MAX_HOST_DELAY_MS))
+    }
+}
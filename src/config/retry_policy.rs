@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+/// 重试策略配置
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（包含首次尝试）
+    pub max_attempts: u32,
+    /// 首次重试前的基础等待时间（毫秒）
+    pub base_delay_ms: u64,
+    /// 单次等待时间上限（毫秒）
+    pub max_delay_ms: u64,
+    /// 是否在等待时间上叠加随机抖动
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 1000,
+            max_delay_ms: 30_000,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次尝试失败后，距离下一次尝试的等待时间
+    ///
+    /// 等待时间按指数增长（base * 2^(attempt-1)），不超过上限；
+    /// 启用抖动时在 [delay/2, delay] 区间内随机取值，避免大量任务同时重试。
+    pub fn backoff_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let delay_ms = self
+            .base_delay_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_delay_ms);
+
+        let delay_ms = if self.jitter && delay_ms > 1 {
+            rand::random_range(delay_ms / 2..=delay_ms)
+        } else {
+            delay_ms
+        };
+
+        Duration::from_millis(delay_ms)
+    }
+
+    /// 是否还可以继续尝试
+    pub fn should_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(jitter: bool) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay_ms: 1000,
+            max_delay_ms: 5000,
+            jitter,
+        }
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_the_cap() {
+        let policy = policy(false);
+        let delays: Vec<u64> = (1..=5)
+            .map(|attempt| policy.backoff_delay(attempt).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [1000, 2000, 4000, 5000, 5000]);
+        assert_eq!(policy.backoff_delay(u32::MAX), Duration::from_millis(5000));
+    }
+
+    #[test]
+    fn jitter_stays_within_half_to_full_delay() {
+        let policy = policy(true);
+        for _ in 0..100 {
+            let delay = policy.backoff_delay(2).as_millis() as u64;
+            assert!((1000..=2000).contains(&delay), "{}", delay);
+        }
+    }
+
+    #[test]
+    fn retries_until_max_attempts() {
+        let policy = policy(false);
+        assert!(policy.should_retry(1));
+        assert!(policy.should_retry(2));
+        assert!(!policy.should_retry(3));
+    }
+}
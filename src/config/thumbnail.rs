@@ -0,0 +1,48 @@
+/// 默认缩略图宽度（像素）
+pub const DEFAULT_THUMBNAIL_WIDTH: u32 = 320;
+
+/// 缩略图格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThumbnailFormat {
+    /// 有损压缩，体积最小
+    #[default]
+    Jpeg,
+    /// 无损压缩，文字边缘更清晰
+    Webp,
+}
+
+impl ThumbnailFormat {
+    /// 根据命令行中的名称解析格式
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "jpeg" | "jpg" => Some(ThumbnailFormat::Jpeg),
+            "webp" => Some(ThumbnailFormat::Webp),
+            _ => None,
+        }
+    }
+
+    /// 缩略图文件的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpeg => "jpg",
+            ThumbnailFormat::Webp => "webp",
+        }
+    }
+}
+
+/// 缩略图配置
+#[derive(Debug, Clone)]
+pub struct ThumbnailConfig {
+    /// 缩略图宽度（像素），高度按比例计算
+    pub width: u32,
+    pub format: ThumbnailFormat,
+}
+
+impl Default for ThumbnailConfig {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_THUMBNAIL_WIDTH,
+            format: ThumbnailFormat::default(),
+        }
+    }
+}
@@ -1,12 +1,23 @@
-use csv::Writer;
-use log::info;
+use csv::{ByteRecord, Reader, ReaderBuilder, StringRecord, Writer, WriterBuilder};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::models::target::Target;
+use crate::analysis::perceptual_hash::PerceptualHash;
+use crate::models::page_class::PageClass;
+use crate::models::page_response::RedirectHop;
+use crate::models::screenshot_error::ScreenshotError;
+use crate::models::target::{ScreenshotResult, Target};
+use crate::models::technology::Technology;
 
 /// 截图结果记录
-#[derive(Debug, Clone)]
+///
+/// 重定向链和响应头等嵌套数据只写入 JSONL 日志，CSV 日志中不保存。
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScreenshotRecord {
     pub timestamp: u64,
     pub target: String,
@@ -14,23 +25,173 @@ pub struct ScreenshotRecord {
     pub success: bool,
     pub error_message: Option<String>,
     pub screenshot_path: Option<String>,
+    pub attempts: u32,
+    pub error_category: Option<String>,
+    pub error_kind: Option<String>,
+    pub status_code: Option<u16>,
+    pub title: Option<String>,
+    /// 页面分类（normal/blank/error_page/default_page），仅成功的截图有值
+    #[serde(default)]
+    pub page_class: Option<String>,
+    /// 截图感知哈希的十六进制表示
+    #[serde(default)]
+    pub perceptual_hash: Option<String>,
+    /// 相似截图分组，运行结束后统一计算
+    #[serde(default)]
+    pub cluster_id: Option<usize>,
+    #[serde(default)]
+    pub cluster_size: Option<usize>,
+    /// 识别出的技术
+    #[serde(default)]
+    pub technologies: Vec<Technology>,
+    /// 页面内容标签（login/admin_panel/directory_listing/dashboard/default_credentials）
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub final_url: Option<String>,
+    #[serde(default)]
+    pub redirect_chain: Vec<RedirectHop>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// 缩略图路径，未生成缩略图时为空
+    #[serde(default)]
+    pub thumbnail_path: Option<String>,
 }
 
+impl ScreenshotRecord {
+    /// 根据截图结果生成记录，时间戳取当前时间
+    pub fn from_result(
+        target: &Target,
+        result: &Result<ScreenshotResult, ScreenshotError>,
+    ) -> Self {
+        let mut record = ScreenshotRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            target: target.original_text.clone(),
+            target_type: target.get_type().to_string(),
+            success: false,
+            error_message: None,
+            screenshot_path: None,
+            attempts: 1,
+            error_category: None,
+            error_kind: None,
+            status_code: None,
+            title: None,
+            page_class: None,
+            perceptual_hash: None,
+            cluster_id: None,
+            cluster_size: None,
+            technologies: Vec::new(),
+            tags: Vec::new(),
+            final_url: None,
+            redirect_chain: Vec::new(),
+            headers: BTreeMap::new(),
+            thumbnail_path: None,
+        };
+
+        match result {
+            Ok(screenshot_result) => {
+                record.success = screenshot_result.success;
+                record.screenshot_path = screenshot_result.file_path.clone();
+                record.thumbnail_path = screenshot_result.thumbnail_path.clone();
+                record.error_message = screenshot_result.error_message.clone();
+                record.attempts = screenshot_result.attempts;
+                record.error_category = screenshot_result.error_category.map(|c| c.to_string());
+                record.error_kind = screenshot_result.error_kind.map(str::to_string);
+                record.status_code = screenshot_result.status_code;
+                record.title = screenshot_result.title.clone();
+                record.page_class = screenshot_result.page_class.map(|c| c.to_string());
+                record.perceptual_hash = screenshot_result
+                    .perceptual_hash
+                    .map(PerceptualHash::to_hex);
+                record.technologies = screenshot_result.technologies.clone();
+                record.tags = screenshot_result
+                    .tags
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                if let Some(response) = &screenshot_result.response {
+                    record.final_url = response.final_url.clone();
+                    record.redirect_chain = response.redirect_chain.clone();
+                    record.headers = response.headers.clone();
+                }
+            }
+            Err(e) => {
+                record.error_message = Some(e.to_string());
+                record.error_category = Some(e.category().to_string());
+                record.error_kind = Some(e.kind().to_string());
+            }
+        }
+
+        record
+    }
+
+    /// 技术列表的单行文本，例如 "PHP 8.1; nginx 1.18"
+    pub fn technologies_label(&self) -> String {
+        self.technologies
+            .iter()
+            .map(Technology::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    /// 是否带有登录页、管理后台等内容标签
+    pub fn is_interesting(&self) -> bool {
+        !self.tags.is_empty()
+    }
+
+    /// 是否为空白页、错误页或默认页等噪音页面
+    pub fn is_noise(&self) -> bool {
+        self.page_class
+            .as_deref()
+            .and_then(PageClass::parse)
+            .is_some_and(|class| class.is_noise())
+    }
+}
+
+/// 输出目录中的CSV日志文件名
+pub const CSV_LOG_FILENAME: &str = "screenshot_log.csv";
+
+/// 输出目录中的JSONL日志文件名
+pub const JSONL_LOG_FILENAME: &str = "screenshot_log.jsonl";
+
+/// CSV日志表头
+const CSV_HEADERS: [&str; 18] = [
+    "timestamp",
+    "target",
+    "target_type",
+    "success",
+    "error_message",
+    "screenshot_path",
+    "attempts",
+    "error_category",
+    "error_kind",
+    "status_code",
+    "title",
+    "page_class",
+    "perceptual_hash",
+    "cluster_id",
+    "cluster_size",
+    "technologies",
+    "tags",
+    "thumbnail_path",
+];
+
 /// 文件操作服务
 pub struct FileOperations;
 
 impl FileOperations {
     /// 从文件解析目标列表
-    pub fn parse_targets_from_file(
-        file_path: &str,
-    ) -> Result<Vec<Target>, Box<dyn std::error::Error>> {
+    pub fn parse_targets_from_file(file_path: &str) -> Result<Vec<Target>, ScreenshotError> {
         info!("正在读取文件: {}", file_path);
         let content = fs::read_to_string(file_path)?;
         let mut targets = Vec::new();
 
         for line in content.lines() {
             if let Some(target) = Target::new(line.to_string()) {
-                targets.push(target);
+                targets.push(target.with_index(targets.len() + 1));
             }
         }
 
@@ -39,7 +200,7 @@ impl FileOperations {
     }
 
     /// 确保截图目录存在
-    pub fn ensure_screenshots_dir(screenshots_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn ensure_screenshots_dir(screenshots_dir: &str) -> Result<(), ScreenshotError> {
         if !Path::new(screenshots_dir).exists() {
             fs::create_dir_all(screenshots_dir)?;
             info!("创建截图文件夹: {}", screenshots_dir);
@@ -48,27 +209,266 @@ impl FileOperations {
     }
 
     /// 创建CSV日志文件
-    pub fn create_csv_log_file(
-        csv_path: &str,
-    ) -> Result<Writer<fs::File>, Box<dyn std::error::Error>> {
+    pub fn create_csv_log_file(csv_path: &str) -> Result<Writer<fs::File>, ScreenshotError> {
         let file = fs::File::create(csv_path)?;
         let mut writer = Writer::from_writer(file);
 
         // 写入CSV表头
-        writer.write_record(&[
-            "timestamp",
-            "target",
-            "target_type",
-            "success",
-            "error_message",
-            "screenshot_path",
-        ])?;
+        writer.write_record(CSV_HEADERS)?;
         writer.flush()?;
 
         info!("创建CSV日志文件: {}", csv_path);
         Ok(writer)
     }
 
+    /// 以追加模式打开CSV日志文件，文件不存在或为空时新建并写入表头
+    ///
+    /// 已有日志的表头与当前版本不同时（旧版本生成的日志），先按当前表头重写，避免追加的行与旧列错位。
+    pub fn open_csv_log_file_for_append(
+        csv_path: &str,
+    ) -> Result<Writer<fs::File>, ScreenshotError> {
+        let is_empty = fs::metadata(csv_path)
+            .map(|metadata| metadata.len() == 0)
+            .unwrap_or(true);
+        if is_empty {
+            return Self::create_csv_log_file(csv_path);
+        }
+
+        let headers = Self::csv_reader(csv_path)?.headers()?.clone();
+        if !headers.iter().eq(CSV_HEADERS) {
+            warn!("CSV日志表头与当前版本不一致，按新表头迁移: {}", csv_path);
+            let records = Self::read_csv_log(csv_path)?;
+            Self::rewrite_csv_log(csv_path, &records)?;
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(csv_path)?;
+        // 上次运行中断时最后一行可能没有写完换行符，补上后再追加
+        file.seek(SeekFrom::End(-1))?;
+        let mut last = [0u8; 1];
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            file.write_all(b"\n")?;
+        }
+
+        info!("追加写入CSV日志文件: {}", csv_path);
+        Ok(Writer::from_writer(file))
+    }
+
+    /// 读取已有的CSV日志记录
+    ///
+    /// 按表头名称取值，兼容缺少新增列的旧版日志和列数不一致的行；无法解析的行会被跳过。
+    pub fn read_csv_log(csv_path: &str) -> Result<Vec<ScreenshotRecord>, ScreenshotError> {
+        let mut reader = Self::csv_reader(csv_path)?;
+        let headers = reader.headers()?.clone();
+
+        let mut records = Vec::new();
+        for (line, row) in reader.byte_records().enumerate() {
+            match row {
+                Ok(row) => match Self::parse_csv_row(&headers, row) {
+                    Some(record) => records.push(record),
+                    None => warn!("CSV日志第 {} 行格式无效，已跳过", line + 2),
+                },
+                Err(e) => warn!("读取CSV日志第 {} 行失败: {}", line + 2, e),
+            }
+        }
+
+        info!("从CSV日志读取 {} 条记录: {}", records.len(), csv_path);
+        Ok(records)
+    }
+
+    /// 读取已有的JSONL日志记录，无法解析的行会被跳过
+    pub fn read_jsonl_log(jsonl_path: &str) -> Result<Vec<ScreenshotRecord>, ScreenshotError> {
+        let content = fs::read_to_string(jsonl_path)?;
+
+        let mut records = Vec::new();
+        for (line, text) in content.lines().enumerate() {
+            if text.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(text) {
+                Ok(record) => records.push(record),
+                Err(e) => warn!("JSONL日志第 {} 行格式无效，已跳过: {}", line + 1, e),
+            }
+        }
+
+        info!("从JSONL日志读取 {} 条记录: {}", records.len(), jsonl_path);
+        Ok(records)
+    }
+
+    /// 按扩展名读取CSV或JSONL日志
+    pub fn read_result_log(log_path: &str) -> Result<Vec<ScreenshotRecord>, ScreenshotError> {
+        let is_jsonl = Path::new(log_path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl"));
+        if is_jsonl {
+            Self::read_jsonl_log(log_path)
+        } else {
+            Self::read_csv_log(log_path)
+        }
+    }
+
+    /// 每个目标只保留最后一条记录，按目标首次出现的顺序排列
+    pub fn latest_records(records: &[ScreenshotRecord]) -> Vec<ScreenshotRecord> {
+        let mut order: Vec<&str> = Vec::new();
+        let mut latest: HashMap<&str, &ScreenshotRecord> = HashMap::new();
+        for record in records {
+            if latest.insert(&record.target, record).is_none() {
+                order.push(&record.target);
+            }
+        }
+
+        order
+            .into_iter()
+            .filter_map(|target| latest.get(target).map(|record| (*record).clone()))
+            .collect()
+    }
+
+    /// 根据已有日志计算续跑时需要跳过的目标
+    ///
+    /// 同一目标有多条记录时以最后一条为准；成功的目标总是跳过，
+    /// 失败的目标仅在不重试失败项时跳过。
+    pub fn completed_targets(records: &[ScreenshotRecord], retry_failed: bool) -> HashSet<String> {
+        Self::latest_records(records)
+            .into_iter()
+            .filter(|record| record.success || !retry_failed)
+            .map(|record| record.target)
+            .collect()
+    }
+
+    /// 不要求每行列数相同的CSV读取器
+    fn csv_reader(csv_path: &str) -> Result<Reader<fs::File>, ScreenshotError> {
+        Ok(ReaderBuilder::new().flexible(true).from_path(csv_path)?)
+    }
+
+    /// 解析原始CSV行，非UTF-8或缺少必需字段时返回 `None`
+    fn parse_csv_row(headers: &StringRecord, row: ByteRecord) -> Option<ScreenshotRecord> {
+        let row = StringRecord::from_byte_record(row).ok()?;
+        Self::parse_csv_record(headers, &row)
+    }
+
+    /// 按表头解析单行CSV记录
+    fn parse_csv_record(headers: &StringRecord, row: &StringRecord) -> Option<ScreenshotRecord> {
+        let field = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header == name)
+                .and_then(|index| row.get(index))
+        };
+        let optional = |name: &str| field(name).filter(|v| !v.is_empty()).map(str::to_string);
+
+        Some(ScreenshotRecord {
+            timestamp: field("timestamp")?.parse().ok()?,
+            target: field("target")?.to_string(),
+            target_type: field("target_type").unwrap_or_default().to_string(),
+            success: field("success")?.parse().ok()?,
+            error_message: optional("error_message"),
+            screenshot_path: optional("screenshot_path"),
+            attempts: field("attempts").and_then(|v| v.parse().ok()).unwrap_or(1),
+            error_category: optional("error_category"),
+            error_kind: optional("error_kind"),
+            status_code: field("status_code").and_then(|v| v.parse().ok()),
+            title: optional("title"),
+            page_class: optional("page_class"),
+            perceptual_hash: optional("perceptual_hash"),
+            cluster_id: field("cluster_id").and_then(|v| v.parse().ok()),
+            cluster_size: field("cluster_size").and_then(|v| v.parse().ok()),
+            technologies: field("technologies")
+                .map(|v| v.split(';').filter_map(Technology::parse).collect())
+                .unwrap_or_default(),
+            tags: field("tags")
+                .map(|v| {
+                    v.split(';')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            final_url: None,
+            redirect_chain: Vec::new(),
+            headers: BTreeMap::new(),
+            thumbnail_path: optional("thumbnail_path"),
+        })
+    }
+
+    /// 用更新后的记录重写CSV日志，表头同时更新为当前版本
+    ///
+    /// `records` 须是 `read_csv_log` 从同一文件按顺序读出的记录；无法解析的行原样保留在原位置。
+    /// 先写入同目录下的临时文件再替换原文件，中途失败不会损坏原日志。
+    pub fn rewrite_csv_log(
+        csv_path: &str,
+        records: &[ScreenshotRecord],
+    ) -> Result<(), ScreenshotError> {
+        let mut reader = Self::csv_reader(csv_path)?;
+        let headers = reader.headers()?.clone();
+
+        let temp_path = format!("{}.tmp", csv_path);
+        let result = (|| {
+            // 保留的原始行可能与表头列数不同
+            let mut writer = WriterBuilder::new().flexible(true).from_path(&temp_path)?;
+            writer.write_record(CSV_HEADERS)?;
+            let mut records = records.iter();
+            for row in reader.byte_records() {
+                let row = row?;
+                if Self::parse_csv_row(&headers, row.clone()).is_some() {
+                    if let Some(record) = records.next() {
+                        Self::log_csv_record(&mut writer, record)?;
+                    }
+                } else {
+                    writer.write_byte_record(&row)?;
+                }
+            }
+            for record in records {
+                Self::log_csv_record(&mut writer, record)?;
+            }
+
+            writer.flush()?;
+            writer
+                .into_inner()
+                .map_err(|e| ScreenshotError::Io(e.into_error()))?
+                .sync_all()?;
+            fs::rename(&temp_path, csv_path)?;
+            Ok(())
+        })();
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    }
+
+    /// 创建JSONL日志文件，`append` 为真时追加到已有文件
+    pub fn create_jsonl_log_file(
+        jsonl_path: &str,
+        append: bool,
+    ) -> Result<BufWriter<fs::File>, ScreenshotError> {
+        let file = if append {
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(jsonl_path)?
+        } else {
+            fs::File::create(jsonl_path)?
+        };
+
+        info!("创建JSONL日志文件: {}", jsonl_path);
+        Ok(BufWriter::new(file))
+    }
+
+    /// 写入一行JSONL记录并立即刷新
+    pub fn log_jsonl_record<W: Write>(
+        jsonl_writer: &mut W,
+        record: &ScreenshotRecord,
+    ) -> Result<(), ScreenshotError> {
+        serde_json::to_writer(&mut *jsonl_writer, record)?;
+        jsonl_writer.write_all(b"\n")?;
+        jsonl_writer.flush()?;
+        Ok(())
+    }
+
     /// 检查文件是否存在
     pub fn file_exists(file_path: &str) -> bool {
         Path::new(file_path).exists()
@@ -78,16 +478,175 @@ impl FileOperations {
     pub fn log_csv_record(
         csv_writer: &mut Writer<fs::File>,
         record: &ScreenshotRecord,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        csv_writer.write_record(&[
+    ) -> Result<(), ScreenshotError> {
+        csv_writer.write_record([
             &record.timestamp.to_string(),
             &record.target,
             &record.target_type,
             &record.success.to_string(),
-            &record.error_message.as_ref().unwrap_or(&"".to_string()),
-            &record.screenshot_path.as_ref().unwrap_or(&"".to_string()),
+            record.error_message.as_deref().unwrap_or(""),
+            record.screenshot_path.as_deref().unwrap_or(""),
+            &record.attempts.to_string(),
+            record.error_category.as_deref().unwrap_or(""),
+            record.error_kind.as_deref().unwrap_or(""),
+            &record
+                .status_code
+                .map(|code| code.to_string())
+                .unwrap_or_default(),
+            record.title.as_deref().unwrap_or(""),
+            record.page_class.as_deref().unwrap_or(""),
+            record.perceptual_hash.as_deref().unwrap_or(""),
+            &record
+                .cluster_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            &record
+                .cluster_size
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+            &record.technologies_label(),
+            &record.tags.join("; "),
+            record.thumbnail_path.as_deref().unwrap_or(""),
         ])?;
         csv_writer.flush()?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_csv(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("screenshot_log.csv");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    const OLD_LOG: &str = "timestamp,target,target_type,success,error_message,screenshot_path\n\
+        100,http://a.example,url,true,,a.png\n\
+        101,http://b.example,url,false,timeout\n\
+        broken,http://c.example,url,true,,c.png\n\
+        102,http://d.example,url,true,,d.png,extra\n";
+
+    #[test]
+    fn reads_rows_with_missing_or_extra_columns() {
+        let path = temp_csv("read", OLD_LOG);
+        let records = FileOperations::read_csv_log(path.to_str().unwrap()).unwrap();
+
+        let targets: Vec<&str> = records.iter().map(|r| r.target.as_str()).collect();
+        assert_eq!(
+            targets,
+            ["http://a.example", "http://b.example", "http://d.example"]
+        );
+        assert_eq!(records[0].screenshot_path.as_deref(), Some("a.png"));
+        assert_eq!(records[1].error_message.as_deref(), Some("timeout"));
+        assert_eq!(records[1].screenshot_path, None);
+        assert_eq!(records[1].attempts, 1);
+    }
+
+    #[test]
+    fn rewrite_keeps_unparsed_rows_in_place() {
+        let path = temp_csv("rewrite", OLD_LOG);
+        let csv_path = path.to_str().unwrap();
+        let mut records = FileOperations::read_csv_log(csv_path).unwrap();
+        records[0].cluster_id = Some(7);
+
+        FileOperations::rewrite_csv_log(csv_path, &records).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], CSV_HEADERS.join(","));
+        assert_eq!(lines[3], "broken,http://c.example,url,true,,c.png");
+        assert!(!path.with_extension("csv.tmp").exists());
+
+        let reread = FileOperations::read_csv_log(csv_path).unwrap();
+        assert_eq!(reread.len(), 3);
+        assert_eq!(reread[0].cluster_id, Some(7));
+        assert_eq!(reread[2].target, "http://d.example");
+    }
+
+    #[test]
+    fn failed_rewrite_leaves_the_log_untouched() {
+        let path = temp_csv("rewrite-fail", OLD_LOG);
+        // 临时文件路径被目录占用，写入必然失败
+        fs::create_dir_all(path.with_extension("csv.tmp")).unwrap();
+
+        let result = FileOperations::rewrite_csv_log(path.to_str().unwrap(), &[]);
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), OLD_LOG);
+    }
+
+    fn record(target: &str, success: bool) -> ScreenshotRecord {
+        let line = format!("1,{},url,{}", target, success);
+        let headers = StringRecord::from(vec!["timestamp", "target", "target_type", "success"]);
+        let row = StringRecord::from(line.split(',').collect::<Vec<_>>());
+        FileOperations::parse_csv_record(&headers, &row).unwrap()
+    }
+
+    #[test]
+    fn resume_uses_the_latest_record_per_target() {
+        let records = [
+            record("a", false),
+            record("a", true),
+            record("b", true),
+            record("b", false),
+            record("c", false),
+        ];
+
+        let skipped = FileOperations::completed_targets(&records, false);
+        assert_eq!(skipped, HashSet::from(["a", "b", "c"].map(String::from)));
+
+        let skipped = FileOperations::completed_targets(&records, true);
+        assert_eq!(skipped, HashSet::from(["a".to_string()]));
+    }
+
+    #[test]
+    fn append_migrates_an_older_header() {
+        let path = temp_csv("append-migrate", OLD_LOG);
+        let csv_path = path.to_str().unwrap();
+
+        let mut writer = FileOperations::open_csv_log_file_for_append(csv_path).unwrap();
+        FileOperations::log_csv_record(&mut writer, &record("http://e.example", true)).unwrap();
+        drop(writer);
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with(&CSV_HEADERS.join(",")));
+        let targets: Vec<String> = FileOperations::read_csv_log(csv_path)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.target)
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                "http://a.example",
+                "http://b.example",
+                "http://d.example",
+                "http://e.example"
+            ]
+        );
+    }
+
+    #[test]
+    fn append_starts_a_new_line_after_a_truncated_row() {
+        let header = CSV_HEADERS.join(",");
+        let path = temp_csv(
+            "append-truncated",
+            &format!("{}\n1,http://a.example,url,tr", header),
+        );
+        let csv_path = path.to_str().unwrap();
+
+        let mut writer = FileOperations::open_csv_log_file_for_append(csv_path).unwrap();
+        FileOperations::log_csv_record(&mut writer, &record("http://b.example", true)).unwrap();
+        drop(writer);
+
+        let records = FileOperations::read_csv_log(csv_path).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].target, "http://b.example");
+    }
+}
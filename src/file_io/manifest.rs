@@ -0,0 +1,316 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::file_io::file_operations::ScreenshotRecord;
+use crate::file_io::run_archive::RunArchive;
+use crate::models::screenshot_error::ScreenshotError;
+
+/// 清单文件名
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// 输出目录的完整性清单，记录每个文件的 SHA-256，用于证明截图和日志未被改动
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// 生成清单的工具名称
+    pub tool: String,
+    /// 生成清单的工具版本
+    pub tool_version: String,
+    /// 本次运行的ID
+    pub run_id: String,
+    /// 清单生成时间（RFC 3339，UTC）
+    pub created_at: String,
+    /// 按路径排序的文件列表
+    pub files: Vec<ManifestEntry>,
+}
+
+/// 清单中的单个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// 相对于输出目录的路径，统一使用 '/' 分隔
+    pub path: String,
+    /// 文件内容的 SHA-256（小写十六进制）
+    pub sha256: String,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 截图文件为截图时间，其他文件为最后修改时间（RFC 3339，UTC）
+    pub captured_at: Option<String>,
+}
+
+impl ManifestEntry {
+    /// `captured_at` 为截图时间的Unix秒数，为空时取文件的最后修改时间
+    pub fn new(
+        sha256: String,
+        metadata: &fs::Metadata,
+        captured_at: Option<u64>,
+        path: String,
+    ) -> Self {
+        let captured_at = captured_at
+            .and_then(|timestamp| DateTime::from_timestamp(timestamp as i64, 0))
+            .or_else(|| metadata.modified().ok().map(DateTime::<Utc>::from))
+            .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true));
+        Self {
+            path,
+            sha256,
+            size: metadata.len(),
+            captured_at,
+        }
+    }
+}
+
+/// 按清单校验目录的结果
+#[derive(Debug, Default)]
+pub struct ManifestCheck {
+    /// 内容与清单一致的文件数
+    pub verified: usize,
+    /// 内容或大小与清单不一致的文件
+    pub tampered: Vec<String>,
+    /// 清单中有但目录中不存在的文件
+    pub missing: Vec<String>,
+    /// 目录中有但清单中没有的文件
+    pub untracked: Vec<String>,
+}
+
+impl ManifestCheck {
+    /// 清单中的文件都存在且未被改动
+    pub fn is_intact(&self) -> bool {
+        self.tampered.is_empty() && self.missing.is_empty()
+    }
+}
+
+impl Manifest {
+    /// 计算 `dir` 下所有文件（清单本身除外）的哈希，`records` 用于查找截图时间
+    pub fn build(
+        dir: &str,
+        run_id: &str,
+        records: &[ScreenshotRecord],
+    ) -> Result<Self, ScreenshotError> {
+        let mut files = Vec::new();
+        for (path, full_path) in Self::list_files(dir)? {
+            let metadata = fs::metadata(&full_path)?;
+            files.push(ManifestEntry::new(
+                Self::sha256_file(&full_path)?,
+                &metadata,
+                Self::capture_time(records, &path),
+                path,
+            ));
+        }
+        Ok(Self::from_entries(run_id, files))
+    }
+
+    /// 由已计算好的文件列表生成清单
+    pub fn from_entries(run_id: &str, mut files: Vec<ManifestEntry>) -> Self {
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Self {
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            run_id: run_id.to_string(),
+            created_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            files,
+        }
+    }
+
+    /// 在日志记录中查找相对路径为 `path` 的截图的截图时间
+    pub fn capture_time(records: &[ScreenshotRecord], path: &str) -> Option<u64> {
+        records.iter().find_map(|record| {
+            let screenshot = record.screenshot_path.as_deref()?.replace('\\', "/");
+            (screenshot == path || screenshot.ends_with(&format!("/{}", path)))
+                .then_some(record.timestamp)
+        })
+    }
+
+    /// 写入 `dir` 下的清单文件，返回清单路径
+    pub fn write(&self, dir: &str) -> Result<String, ScreenshotError> {
+        let path = Path::new(dir).join(MANIFEST_FILENAME);
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// 读取清单文件
+    pub fn load(path: &str) -> Result<Self, ScreenshotError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| ScreenshotError::Other(format!("读取清单失败 {}: {}", path, e)))?;
+        serde_json::from_str(&text)
+            .map_err(|e| ScreenshotError::Other(format!("清单格式错误 {}: {}", path, e)))
+    }
+
+    /// 读取运行归档中的清单
+    pub fn load_from_archive(archive_path: &str) -> Result<Self, ScreenshotError> {
+        let mut text = None;
+        RunArchive::read_entries(archive_path, |name, reader| {
+            if name == MANIFEST_FILENAME {
+                let mut content = String::new();
+                reader.read_to_string(&mut content)?;
+                text = Some(content);
+            }
+            Ok(())
+        })?;
+        let text = text.ok_or_else(|| {
+            ScreenshotError::Other(format!(
+                "归档中没有 {}: {}",
+                MANIFEST_FILENAME, archive_path
+            ))
+        })?;
+        serde_json::from_str(&text)
+            .map_err(|e| ScreenshotError::Other(format!("清单格式错误 {}: {}", archive_path, e)))
+    }
+
+    /// 重新计算 `dir` 中文件的哈希并与清单比对
+    pub fn verify(&self, dir: &str) -> Result<ManifestCheck, ScreenshotError> {
+        let present: BTreeMap<String, PathBuf> = Self::list_files(dir)?.into_iter().collect();
+        self.compare(present, |full_path| {
+            Ok((
+                fs::metadata(full_path)?.len(),
+                Self::sha256_file(full_path)?,
+            ))
+        })
+    }
+
+    /// 重新计算运行归档中文件的哈希并与清单比对
+    pub fn verify_archive(&self, archive_path: &str) -> Result<ManifestCheck, ScreenshotError> {
+        let mut present: BTreeMap<String, (u64, String)> = BTreeMap::new();
+        RunArchive::read_entries(archive_path, |name, reader| {
+            if name != MANIFEST_FILENAME {
+                let mut hasher = Sha256::new();
+                let size = io::copy(reader, &mut hasher)?;
+                present.insert(name.to_string(), (size, format!("{:x}", hasher.finalize())));
+            }
+            Ok(())
+        })?;
+        self.compare(present, |digest| Ok(digest.clone()))
+    }
+
+    /// 逐项比对清单和实际存在的文件，`digest` 计算文件的大小和 SHA-256
+    fn compare<T>(
+        &self,
+        mut present: BTreeMap<String, T>,
+        digest: impl Fn(&T) -> Result<(u64, String), ScreenshotError>,
+    ) -> Result<ManifestCheck, ScreenshotError> {
+        let mut check = ManifestCheck::default();
+        for entry in &self.files {
+            let Some(file) = present.remove(&entry.path) else {
+                check.missing.push(entry.path.clone());
+                continue;
+            };
+            let (size, sha256) = digest(&file)?;
+            let intact = size == entry.size && sha256.eq_ignore_ascii_case(&entry.sha256);
+            if intact {
+                check.verified += 1;
+            } else {
+                check.tampered.push(entry.path.clone());
+            }
+        }
+        check.untracked = present.into_keys().collect();
+        Ok(check)
+    }
+
+    /// 计算文件的 SHA-256
+    pub fn sha256_file(path: &Path) -> io::Result<String> {
+        let mut hasher = Sha256::new();
+        io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// 递归列出目录中的文件（清单本身除外），返回按相对路径排序的 (相对路径, 完整路径)
+    pub fn list_files(dir: &str) -> Result<Vec<(String, PathBuf)>, ScreenshotError> {
+        let root = Path::new(dir);
+        let mut files = Vec::new();
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            for entry in fs::read_dir(&current)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let relative = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                if relative != MANIFEST_FILENAME {
+                    files.push((relative, path));
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::archive_format::ArchiveFormat;
+
+    /// 创建包含两个文件的输出目录
+    fn output_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.png"), b"png data").unwrap();
+        fs::write(dir.join("sub").join("b.csv"), b"csv data").unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn directory_round_trip_detects_changes() {
+        let dir = output_dir("manifest-dir");
+        let manifest = Manifest::build(&dir, "run", &[]).unwrap();
+        let manifest_path = manifest.write(&dir).unwrap();
+
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.png", "sub/b.csv"]);
+        let loaded = Manifest::load(&manifest_path).unwrap();
+        let check = loaded.verify(&dir).unwrap();
+        assert!(check.is_intact());
+        assert_eq!(check.verified, 2);
+        assert!(check.untracked.is_empty());
+
+        let root = Path::new(&dir);
+        fs::write(root.join("a.png"), b"edited!!").unwrap();
+        fs::remove_file(root.join("sub").join("b.csv")).unwrap();
+        fs::write(root.join("c.txt"), b"new").unwrap();
+        let check = loaded.verify(&dir).unwrap();
+        assert!(!check.is_intact());
+        assert_eq!(check.tampered, ["a.png"]);
+        assert_eq!(check.missing, ["sub/b.csv"]);
+        assert_eq!(check.untracked, ["c.txt"]);
+    }
+
+    #[test]
+    fn archive_round_trip() {
+        for format in [ArchiveFormat::Zip, ArchiveFormat::TarGz] {
+            let dir = output_dir(&format!("manifest-{}", format.extension()));
+            let archive = RunArchive::create(&dir, format).unwrap();
+            let outputs = ["a.png", "sub/b.csv"]
+                .map(|name| Path::new(&dir).join(name).to_string_lossy().to_string());
+            let archive_path = archive.finish("run", &[], &outputs).unwrap();
+
+            assert_eq!(
+                RunArchive::find(&dir).as_deref(),
+                Some(archive_path.as_str())
+            );
+            let manifest = Manifest::load_from_archive(&archive_path).unwrap();
+            assert_eq!(manifest.run_id, "run");
+            assert_eq!(manifest.files.len(), 2);
+
+            let check = manifest.verify_archive(&archive_path).unwrap();
+            assert!(check.is_intact());
+            assert_eq!(check.verified, 2);
+            assert!(check.untracked.is_empty());
+
+            let mut edited = manifest.clone();
+            edited.files[0].sha256 = "0".repeat(64);
+            let check = edited.verify_archive(&archive_path).unwrap();
+            assert_eq!(check.tampered, ["a.png"]);
+            fs::remove_file(&archive_path).unwrap();
+        }
+    }
+}
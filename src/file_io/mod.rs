@@ -3,5 +3,13 @@
 //! 包含文件读写和目录操作功能
 
 pub mod file_operations;
+pub mod manifest;
+pub mod output_naming;
+pub mod result_sink;
+pub mod run_archive;
+pub mod sqlite_store;
 
 pub use file_operations::{FileOperations, ScreenshotRecord};
+pub use manifest::Manifest;
+pub use result_sink::{ResultSink, RunSummary};
+pub use sqlite_store::SqliteStore;
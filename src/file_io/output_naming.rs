@@ -0,0 +1,336 @@
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+
+use crate::models::screenshot_error::ScreenshotError;
+use crate::models::target::Target;
+
+/// 默认命名模板，与早期版本的文件名一致
+pub const DEFAULT_NAMING_TEMPLATE: &str = "{name}_{timestamp}.{ext}";
+
+/// 同名文件最多尝试的序号
+const MAX_SUFFIX: u32 = 10_000;
+
+/// 命名模板中可用的占位符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    /// 清理后的原始目标文本
+    Name,
+    /// 主机名
+    Host,
+    /// 端口（未指定时按协议取默认端口）
+    Port,
+    /// 目标在输入文件中的序号（从1开始）
+    Index,
+    /// 截图日期（UTC，YYYYMMDD）
+    Date,
+    /// 截图时间（UTC，HHMMSS）
+    Time,
+    /// 截图时间的Unix秒数
+    Timestamp,
+    /// 文件内容 SHA-256 的前12位
+    Hash,
+    /// 配置档名称
+    Profile,
+    /// 运行ID
+    RunId,
+    /// 扩展名
+    Ext,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "name" => Some(Field::Name),
+            "host" => Some(Field::Host),
+            "port" => Some(Field::Port),
+            "index" => Some(Field::Index),
+            "date" => Some(Field::Date),
+            "time" => Some(Field::Time),
+            "timestamp" => Some(Field::Timestamp),
+            "hash" => Some(Field::Hash),
+            "profile" => Some(Field::Profile),
+            "run_id" => Some(Field::RunId),
+            "ext" => Some(Field::Ext),
+            _ => None,
+        }
+    }
+}
+
+/// 模板中的一段
+#[derive(Debug, Clone)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/// 生成文件名时用到的信息
+pub struct NamingContext<'a> {
+    pub target: &'a Target,
+    pub profile: &'a str,
+    pub run_id: &'a str,
+    pub captured_at: DateTime<Utc>,
+    pub ext: &'a str,
+}
+
+/// 按模板生成截图在输出目录中的相对路径
+///
+/// 模板中的 `/` 表示子目录，例如 `{host}/{port}/{date}_{profile}.{ext}`；
+/// 占位符的取值中的路径分隔符和 Windows 不允许的字符会被替换为 `_`，不会产生额外的目录层级。
+#[derive(Debug, Clone)]
+pub struct OutputNaming {
+    parts: Vec<Part>,
+}
+
+impl Default for OutputNaming {
+    fn default() -> Self {
+        Self::new(DEFAULT_NAMING_TEMPLATE).expect("默认命名模板格式错误")
+    }
+}
+
+impl OutputNaming {
+    /// 解析命名模板，模板中没有 `{ext}` 时自动在末尾加上扩展名
+    pub fn new(template: &str) -> Result<Self, ScreenshotError> {
+        let invalid =
+            |reason: &str| ScreenshotError::Other(format!("命名模板无效 {}: {}", template, reason));
+
+        if template.trim().is_empty() {
+            return Err(invalid("模板为空"));
+        }
+        if template.starts_with(['/', '\\']) || template.contains(':') {
+            return Err(invalid("只能使用相对路径"));
+        }
+        if template
+            .split(['/', '\\'])
+            .any(|segment| segment == "." || segment == "..")
+        {
+            return Err(invalid("不能包含 . 或 .. 目录"));
+        }
+
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..].find('}').ok_or_else(|| invalid("缺少 }"))?;
+            let name = &rest[start + 1..start + end];
+            let field =
+                Field::parse(name).ok_or_else(|| invalid(&format!("未知的占位符 {{{}}}", name)))?;
+            parts.push(Part::Field(field));
+            rest = &rest[start + end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(invalid("缺少 {"));
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        if !parts
+            .iter()
+            .any(|part| matches!(part, Part::Field(Field::Ext)))
+        {
+            parts.push(Part::Literal(".".to_string()));
+            parts.push(Part::Field(Field::Ext));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// 生成相对路径的各级目录和文件名
+    pub fn render(&self, context: &NamingContext, data: &[u8]) -> Vec<String> {
+        let (host, port) = context.target.get_host_and_port();
+        let mut path = String::new();
+        for part in &self.parts {
+            let value = match part {
+                Part::Literal(text) => {
+                    path.push_str(&text.replace('\\', "/"));
+                    continue;
+                }
+                Part::Field(Field::Name) => context.target.get_clean_filename(),
+                Part::Field(Field::Host) => host.clone(),
+                Part::Field(Field::Port) => port.to_string(),
+                Part::Field(Field::Index) => context.target.index.to_string(),
+                Part::Field(Field::Date) => context.captured_at.format("%Y%m%d").to_string(),
+                Part::Field(Field::Time) => context.captured_at.format("%H%M%S").to_string(),
+                Part::Field(Field::Timestamp) => context.captured_at.timestamp().to_string(),
+                Part::Field(Field::Hash) => format!("{:x}", Sha256::digest(data))[..12].to_string(),
+                Part::Field(Field::Profile) => context.profile.to_string(),
+                Part::Field(Field::RunId) => context.run_id.to_string(),
+                Part::Field(Field::Ext) => context.ext.to_string(),
+            };
+            path.push_str(&Self::sanitize(&value));
+        }
+
+        // Windows 会去掉末尾的点和空格，只剩点的目录名（如 `..`）改为 `_`
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment.trim_end_matches(['.', ' ']) {
+                "" => "_".to_string(),
+                trimmed => trimmed.to_string(),
+            })
+            .collect()
+    }
+
+    /// 在 `dir` 下按模板创建文件并写入 `data`，返回文件路径
+    ///
+    /// 文件已存在时在扩展名前依次加上 `_1`、`_2`……，以独占方式创建，并发截图也不会互相覆盖。
+    pub fn save(
+        &self,
+        dir: &str,
+        context: &NamingContext,
+        data: &[u8],
+    ) -> Result<String, ScreenshotError> {
+        let mut segments = self.render(context, data);
+        let file_name = segments
+            .pop()
+            .unwrap_or_else(|| format!("_.{}", context.ext));
+
+        let mut parent = PathBuf::from(dir);
+        parent.extend(&segments);
+        fs::create_dir_all(&parent)?;
+
+        let (stem, ext) = match file_name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => (stem.to_string(), format!(".{}", ext)),
+            _ => (file_name.clone(), String::new()),
+        };
+        for suffix in 0..MAX_SUFFIX {
+            let candidate = if suffix == 0 {
+                file_name.clone()
+            } else {
+                format!("{}_{}{}", stem, suffix, ext)
+            };
+            let path = parent.join(candidate);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(data)?;
+                    return Ok(path.to_string_lossy().to_string());
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Err(ScreenshotError::Other(format!(
+            "同名文件过多，无法保存截图: {}",
+            parent.join(file_name).display()
+        )))
+    }
+
+    /// 替换路径分隔符、Windows 文件名不允许的字符和控制字符
+    fn sanitize(value: &str) -> String {
+        value
+            .chars()
+            .map(|c| match c {
+                '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, url: &str) -> Vec<String> {
+        let target = Target::new(url.to_string()).unwrap().with_index(7);
+        let context = NamingContext {
+            target: &target,
+            profile: "client/a",
+            run_id: "run1",
+            captured_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            ext: "png",
+        };
+        OutputNaming::new(template)
+            .unwrap()
+            .render(&context, b"data")
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        for template in [
+            "",
+            "/abs/{host}",
+            "C:{host}",
+            "../{host}",
+            "{host}/./x",
+            "{nope}",
+            "{host",
+            "host}",
+        ] {
+            assert!(OutputNaming::new(template).is_err(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn renders_fields_and_subdirectories() {
+        assert_eq!(
+            render(
+                "{host}/{port}/{date}_{time}_{index}_{run_id}",
+                "https://Example.com/login"
+            ),
+            ["example.com", "443", "20231114_221320_7_run1.png"]
+        );
+        assert_eq!(
+            render("{timestamp}.{ext}", "http://a.example:8080"),
+            ["1700000000.png"]
+        );
+        let hash = format!("{:x}", Sha256::digest(b"data"));
+        assert_eq!(
+            render("{hash}", "http://a.example"),
+            [format!("{}.png", &hash[..12])]
+        );
+    }
+
+    #[test]
+    fn field_values_cannot_add_directories() {
+        // 配置档名称中的 '/' 被替换，不会多出一级目录
+        assert_eq!(
+            render("{profile}\\{host}", "http://a.example"),
+            ["client_a", "a.example.png"]
+        );
+        assert_eq!(render("{host}/x", "http://[::1]:8080/"), ["__1", "x.png"]);
+    }
+
+    #[test]
+    fn trailing_dots_and_dot_only_segments_are_replaced() {
+        assert_eq!(
+            render(".../{host}. /a", "http://a.example"),
+            ["_", "a.example", "a.png"]
+        );
+        assert_eq!(OutputNaming::sanitize("a:b*c?d\u{7}"), "a_b_c_d_");
+    }
+
+    #[test]
+    fn save_adds_suffixes_on_collision() {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-naming-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let dir = dir.to_string_lossy().to_string();
+
+        let target = Target::new("http://a.example".to_string()).unwrap();
+        let context = NamingContext {
+            target: &target,
+            profile: "default",
+            run_id: "run1",
+            captured_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            ext: "png",
+        };
+        let naming = OutputNaming::new("{host}/shot").unwrap();
+
+        let paths: Vec<String> = (0..3)
+            .map(|i| naming.save(&dir, &context, &[i]).unwrap())
+            .collect();
+        let names: Vec<&str> = paths
+            .iter()
+            .map(|path| path.rsplit(['/', '\\']).next().unwrap())
+            .collect();
+        assert_eq!(names, ["shot.png", "shot_1.png", "shot_2.png"]);
+        for (i, path) in paths.iter().enumerate() {
+            assert_eq!(fs::read(path).unwrap(), [i as u8]);
+        }
+    }
+}
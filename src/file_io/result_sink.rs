@@ -0,0 +1,353 @@
+use csv::Writer;
+use log::{error, info};
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::file_io::file_operations::{FileOperations, ScreenshotRecord};
+use crate::file_io::run_archive::RunArchive;
+use crate::file_io::sqlite_store::SqliteStore;
+use crate::models::screenshot_error::ScreenshotError;
+
+/// 一次运行结束时的统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RunSummary {
+    pub success_count: usize,
+    pub fail_count: usize,
+    /// 是否因停止信号提前结束
+    pub interrupted: bool,
+}
+
+/// 截图结果的输出目标
+///
+/// 运行开始前调用一次 `open`，每完成一个目标调用一次 `record`，运行结束后调用一次 `finish`。
+pub trait ResultSink: Send {
+    /// 准备输出（创建文件、连接数据库等）
+    fn open(&mut self) -> Result<(), ScreenshotError>;
+
+    /// 写入一条结果
+    fn record(&mut self, record: &ScreenshotRecord) -> Result<(), ScreenshotError>;
+
+    /// 写入剩余数据并关闭输出
+    fn finish(&mut self, summary: &RunSummary) -> Result<(), ScreenshotError>;
+}
+
+/// CSV 日志
+pub struct CsvSink {
+    path: String,
+    append: bool,
+    writer: Option<Writer<fs::File>>,
+}
+
+impl CsvSink {
+    /// `append` 为真时追加到已有日志，否则覆盖
+    pub fn new(path: impl Into<String>, append: bool) -> Self {
+        Self {
+            path: path.into(),
+            append,
+            writer: None,
+        }
+    }
+}
+
+impl ResultSink for CsvSink {
+    fn open(&mut self) -> Result<(), ScreenshotError> {
+        let writer = if self.append {
+            FileOperations::open_csv_log_file_for_append(&self.path)?
+        } else {
+            FileOperations::create_csv_log_file(&self.path)?
+        };
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    fn record(&mut self, record: &ScreenshotRecord) -> Result<(), ScreenshotError> {
+        match &mut self.writer {
+            Some(writer) => FileOperations::log_csv_record(writer, record),
+            None => Err(ScreenshotError::Other("CSV日志尚未打开".to_string())),
+        }
+    }
+
+    fn finish(&mut self, _summary: &RunSummary) -> Result<(), ScreenshotError> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// JSON Lines 日志，包含响应头和重定向链
+pub struct JsonlSink {
+    path: String,
+    append: bool,
+    writer: Option<BufWriter<fs::File>>,
+}
+
+impl JsonlSink {
+    /// `append` 为真时追加到已有日志，否则覆盖
+    pub fn new(path: impl Into<String>, append: bool) -> Self {
+        Self {
+            path: path.into(),
+            append,
+            writer: None,
+        }
+    }
+}
+
+impl ResultSink for JsonlSink {
+    fn open(&mut self) -> Result<(), ScreenshotError> {
+        self.writer = Some(FileOperations::create_jsonl_log_file(
+            &self.path,
+            self.append,
+        )?);
+        Ok(())
+    }
+
+    fn record(&mut self, record: &ScreenshotRecord) -> Result<(), ScreenshotError> {
+        match &mut self.writer {
+            Some(writer) => FileOperations::log_jsonl_record(writer, record),
+            None => Err(ScreenshotError::Other("JSONL日志尚未打开".to_string())),
+        }
+    }
+
+    fn finish(&mut self, _summary: &RunSummary) -> Result<(), ScreenshotError> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// 以 JSON Lines 格式输出到标准输出，便于管道处理
+#[derive(Default)]
+pub struct StdoutSink;
+
+impl StdoutSink {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ResultSink for StdoutSink {
+    fn open(&mut self) -> Result<(), ScreenshotError> {
+        Ok(())
+    }
+
+    fn record(&mut self, record: &ScreenshotRecord) -> Result<(), ScreenshotError> {
+        FileOperations::log_jsonl_record(&mut io::stdout().lock(), record)
+    }
+
+    fn finish(&mut self, _summary: &RunSummary) -> Result<(), ScreenshotError> {
+        io::stdout().flush()?;
+        Ok(())
+    }
+}
+
+/// SQLite 结果数据库，`open` 时登记一次新运行
+pub struct SqliteSink {
+    db_path: String,
+    screenshots_dir: String,
+    target_count: usize,
+    store: Option<(SqliteStore, i64)>,
+}
+
+impl SqliteSink {
+    pub fn new(
+        db_path: impl Into<String>,
+        screenshots_dir: impl Into<String>,
+        target_count: usize,
+    ) -> Self {
+        Self {
+            db_path: db_path.into(),
+            screenshots_dir: screenshots_dir.into(),
+            target_count,
+            store: None,
+        }
+    }
+}
+
+impl ResultSink for SqliteSink {
+    fn open(&mut self) -> Result<(), ScreenshotError> {
+        let store = SqliteStore::open(&self.db_path)?;
+        let run_id = store.begin_run(&self.screenshots_dir, self.target_count)?;
+        info!("结果数据库 {} 中的本次运行ID: {}", self.db_path, run_id);
+        self.store = Some((store, run_id));
+        Ok(())
+    }
+
+    fn record(&mut self, record: &ScreenshotRecord) -> Result<(), ScreenshotError> {
+        match &mut self.store {
+            Some((store, run_id)) => store.record(*run_id, record),
+            None => Err(ScreenshotError::Other("结果数据库尚未打开".to_string())),
+        }
+    }
+
+    fn finish(&mut self, summary: &RunSummary) -> Result<(), ScreenshotError> {
+        if let Some((store, run_id)) = self.store.take() {
+            store.finish_run(
+                run_id,
+                summary.success_count,
+                summary.fail_count,
+                summary.interrupted,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 截图写入日志后立即写入运行归档
+pub struct ArchiveSink {
+    archive: Arc<Mutex<RunArchive>>,
+}
+
+impl ArchiveSink {
+    pub fn new(archive: Arc<Mutex<RunArchive>>) -> Self {
+        Self { archive }
+    }
+}
+
+impl ResultSink for ArchiveSink {
+    fn open(&mut self) -> Result<(), ScreenshotError> {
+        Ok(())
+    }
+
+    fn record(&mut self, record: &ScreenshotRecord) -> Result<(), ScreenshotError> {
+        self.archive
+            .lock()
+            .map_err(|_| ScreenshotError::Other("运行归档已损坏".to_string()))?
+            .add_screenshot(record)
+    }
+
+    fn finish(&mut self, _summary: &RunSummary) -> Result<(), ScreenshotError> {
+        Ok(())
+    }
+}
+
+/// 同时写入多个输出目标
+///
+/// 单个目标打开、写入或关闭失败只记录日志，不影响其他目标；全部目标都打开失败时 `open` 返回错误。
+#[derive(Default)]
+pub struct MultiSink {
+    sinks: Vec<Box<dyn ResultSink>>,
+}
+
+impl MultiSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加输出目标
+    pub fn with_sink(mut self, sink: impl ResultSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// 添加输出目标
+    pub fn push(&mut self, sink: Box<dyn ResultSink>) {
+        self.sinks.push(sink);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+}
+
+impl ResultSink for MultiSink {
+    fn open(&mut self) -> Result<(), ScreenshotError> {
+        let mut first_error = None;
+        self.sinks.retain_mut(|sink| match sink.open() {
+            Ok(()) => true,
+            Err(e) => {
+                error!("打开结果输出失败，本次运行不再写入: {}", e);
+                first_error.get_or_insert(e);
+                false
+            }
+        });
+        match first_error {
+            Some(e) if self.sinks.is_empty() => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn record(&mut self, record: &ScreenshotRecord) -> Result<(), ScreenshotError> {
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.record(record) {
+                error!("写入结果失败 {}: {}", record.target, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, summary: &RunSummary) -> Result<(), ScreenshotError> {
+        let mut first_error = None;
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.finish(summary) {
+                error!("关闭结果输出失败: {}", e);
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录写入次数的输出目标，`fail_open` 为真时打开失败
+    struct CountingSink {
+        fail_open: bool,
+        records: Arc<Mutex<usize>>,
+    }
+
+    impl ResultSink for CountingSink {
+        fn open(&mut self) -> Result<(), ScreenshotError> {
+            if self.fail_open {
+                return Err(ScreenshotError::Other("数据库被锁定".to_string()));
+            }
+            Ok(())
+        }
+
+        fn record(&mut self, _record: &ScreenshotRecord) -> Result<(), ScreenshotError> {
+            *self.records.lock().unwrap() += 1;
+            Ok(())
+        }
+
+        fn finish(&mut self, _summary: &RunSummary) -> Result<(), ScreenshotError> {
+            Err(ScreenshotError::Other("关闭失败".to_string()))
+        }
+    }
+
+    fn sink(fail_open: bool) -> (CountingSink, Arc<Mutex<usize>>) {
+        let records = Arc::new(Mutex::new(0));
+        let sink = CountingSink {
+            fail_open,
+            records: Arc::clone(&records),
+        };
+        (sink, records)
+    }
+
+    #[test]
+    fn failed_sinks_are_skipped_without_stopping_the_others() {
+        let (working, working_records) = sink(false);
+        let (locked, locked_records) = sink(true);
+        let mut sinks = MultiSink::new().with_sink(locked).with_sink(working);
+        sinks.open().unwrap();
+
+        let record: ScreenshotRecord = serde_json::from_value(serde_json::json!({
+            "timestamp": 1_700_000_000,
+            "target": "http://example.com",
+            "target_type": "url",
+            "success": true,
+            "attempts": 1,
+        }))
+        .unwrap();
+        sinks.record(&record).unwrap();
+        assert_eq!(*working_records.lock().unwrap(), 1);
+        assert_eq!(*locked_records.lock().unwrap(), 0);
+        assert!(sinks.finish(&RunSummary::default()).is_err());
+
+        let (locked, _) = sink(true);
+        assert!(MultiSink::new().with_sink(locked).open().is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use log::warn;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use zip::CompressionMethod;
+use zip::ZipArchive;
+use zip::write::{SimpleFileOptions, ZipWriter};
+
+use crate::config::archive_format::ArchiveFormat;
+use crate::file_io::file_operations::ScreenshotRecord;
+use crate::file_io::manifest::{MANIFEST_FILENAME, Manifest, ManifestEntry};
+use crate::models::screenshot_error::ScreenshotError;
+
+/// 已压缩的图片格式，放入 zip 时不再压缩
+const STORED_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gz", "zip"];
+
+/// 归档写入器
+enum ArchiveWriter {
+    Zip(ZipWriter<BufWriter<File>>),
+    TarGz(tar::Builder<GzEncoder<BufWriter<File>>>),
+}
+
+/// 读取时同时计算 SHA-256，文件只需读一遍
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// 把一次运行的输出打包为单个文件
+///
+/// 只打包本次运行产生的文件：截图和缩略图在写入日志后立即写入归档，
+/// 运行结束后再放入本次的日志、报告和完整性清单，输出目录中的其他文件不受影响。
+/// 运行过程中写入 `<归档>.partial`，全部写完并落盘后才改名为正式归档并删除已归档的原文件；
+/// 中途崩溃或被强制结束时原文件都还在，残留的 `.partial` 可直接删除。
+/// 代价是归档完成前原文件和归档同时占用磁盘，需要预留约两倍于输出的空间。
+pub struct RunArchive {
+    path: String,
+    partial_path: String,
+    dir: String,
+    writer: ArchiveWriter,
+    entries: Vec<ManifestEntry>,
+    /// 已写入归档的原文件，归档完成后删除
+    archived: Vec<PathBuf>,
+    archived_names: HashSet<String>,
+}
+
+impl RunArchive {
+    /// 在输出目录旁创建 `<目录名>.zip` 或 `<目录名>.tar.gz`
+    ///
+    /// 归档已存在或位于输出目录内时返回错误，不覆盖之前的运行结果。
+    pub fn create(dir: &str, format: ArchiveFormat) -> Result<Self, ScreenshotError> {
+        let path = Self::archive_path(dir, format);
+        if Path::new(&path).exists() {
+            return Err(ScreenshotError::Other(format!(
+                "归档已存在，不会覆盖: {}",
+                path
+            )));
+        }
+        if Self::is_inside(dir, &path) {
+            return Err(ScreenshotError::Other(format!(
+                "归档不能放在被打包的目录中: {}",
+                path
+            )));
+        }
+        let partial_path = format!("{}.partial", path);
+        let file = BufWriter::new(File::create(&partial_path)?);
+        let writer = match format {
+            ArchiveFormat::Zip => ArchiveWriter::Zip(ZipWriter::new(file)),
+            ArchiveFormat::TarGz => ArchiveWriter::TarGz(tar::Builder::new(GzEncoder::new(
+                file,
+                Compression::default(),
+            ))),
+        };
+
+        Ok(Self {
+            path,
+            partial_path,
+            dir: dir.to_string(),
+            writer,
+            entries: Vec::new(),
+            archived: Vec::new(),
+            archived_names: HashSet::new(),
+        })
+    }
+
+    /// 放弃归档并删除 `.partial` 文件，原文件不受影响
+    pub fn discard(self) {
+        drop(self.writer);
+        if let Err(e) = fs::remove_file(&self.partial_path) {
+            warn!("删除未完成的归档失败 {}: {}", self.partial_path, e);
+        }
+    }
+
+    /// 归档文件路径
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// 输出目录对应的归档文件路径
+    ///
+    /// `.`、`..` 等没有目录名的路径先转为绝对路径，归档放在上级目录中。
+    pub fn archive_path(dir: &str, format: ArchiveFormat) -> String {
+        let mut base: PathBuf = Path::new(dir).components().collect();
+        if base.file_name().is_none() {
+            base = fs::canonicalize(&base).unwrap_or(base);
+        }
+        format!("{}.{}", base.to_string_lossy(), format.extension())
+    }
+
+    /// `path` 是否位于目录 `dir` 中
+    fn is_inside(dir: &str, path: &str) -> bool {
+        let parent = match Path::new(path).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        match (fs::canonicalize(dir), fs::canonicalize(parent)) {
+            (Ok(dir), Ok(parent)) => parent.starts_with(dir),
+            _ => false,
+        }
+    }
+
+    /// 查找输出目录旁已有的归档文件
+    pub fn find(dir: &str) -> Option<String> {
+        [ArchiveFormat::Zip, ArchiveFormat::TarGz]
+            .into_iter()
+            .map(|format| Self::archive_path(dir, format))
+            .find(|path| Path::new(path).is_file())
+    }
+
+    /// 依次读取归档中的每个文件，`visit` 收到归档内路径（'/' 分隔）和文件内容
+    pub fn read_entries<F>(path: &str, mut visit: F) -> Result<(), ScreenshotError>
+    where
+        F: FnMut(&str, &mut dyn Read) -> Result<(), ScreenshotError>,
+    {
+        let format = ArchiveFormat::detect(path)
+            .ok_or_else(|| ScreenshotError::Other(format!("无法识别的归档格式: {}", path)))?;
+        let file = BufReader::new(File::open(path)?);
+
+        match format {
+            ArchiveFormat::Zip => {
+                let read_error = |e: zip::result::ZipError| {
+                    ScreenshotError::Other(format!("读取zip归档失败: {}", e))
+                };
+                let mut archive = ZipArchive::new(file).map_err(read_error)?;
+                for index in 0..archive.len() {
+                    let mut entry = archive.by_index(index).map_err(read_error)?;
+                    if entry.is_file() {
+                        let name = entry.name().to_string();
+                        visit(&name, &mut entry)?;
+                    }
+                }
+            }
+            ArchiveFormat::TarGz => {
+                let mut archive = tar::Archive::new(GzDecoder::new(file));
+                for entry in archive.entries()? {
+                    let mut entry = entry?;
+                    if entry.header().entry_type().is_file() {
+                        let name = entry.path()?.to_string_lossy().replace('\\', "/");
+                        visit(&name, &mut entry)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 把截图及其缩略图写入归档，截图时间取自日志记录
+    pub fn add_screenshot(&mut self, record: &ScreenshotRecord) -> Result<(), ScreenshotError> {
+        let files = [&record.screenshot_path, &record.thumbnail_path];
+        for path in files.into_iter().flatten() {
+            self.add_file(path, Some(record.timestamp))?;
+        }
+        Ok(())
+    }
+
+    /// 写入本次运行的日志、报告等输出文件和完整性清单，返回归档路径
+    ///
+    /// `outputs` 中不存在或不在输出目录中的文件会被跳过。归档落盘后才删除已归档的文件，
+    /// 清单同时保留在输出目录中，便于不解压就核对归档内容。
+    pub fn finish(
+        mut self,
+        run_id: &str,
+        records: &[ScreenshotRecord],
+        outputs: &[String],
+    ) -> Result<String, ScreenshotError> {
+        // 补上写入日志时未能归档的截图
+        for record in records {
+            self.add_screenshot(record)?;
+        }
+        for path in outputs {
+            if Path::new(path).is_file() {
+                self.add_file(path, None)?;
+            }
+        }
+
+        let manifest = Manifest::from_entries(run_id, std::mem::take(&mut self.entries));
+        let manifest_path = manifest.write(&self.dir)?;
+        self.append(Path::new(&manifest_path), MANIFEST_FILENAME)?;
+
+        let file = match self.writer {
+            ArchiveWriter::Zip(writer) => writer.finish().map_err(Self::zip_error)?,
+            ArchiveWriter::TarGz(builder) => builder.into_inner()?.finish()?,
+        };
+        file.into_inner()
+            .map_err(|e| ScreenshotError::Io(e.into_error()))?
+            .sync_all()?;
+        fs::rename(&self.partial_path, &self.path)?;
+
+        for full_path in &self.archived {
+            if let Err(e) = fs::remove_file(full_path) {
+                warn!("已归档的文件删除失败 {}: {}", full_path.display(), e);
+            }
+        }
+        Ok(self.path)
+    }
+
+    /// 把输出目录中的文件写入归档，已归档或不在输出目录中的文件跳过
+    fn add_file(&mut self, path: &str, captured_at: Option<u64>) -> Result<(), ScreenshotError> {
+        let Ok(relative) = Path::new(path).strip_prefix(&self.dir) else {
+            warn!("文件不在输出目录中，不放入归档: {}", path);
+            return Ok(());
+        };
+        let name = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if name.is_empty() || self.archived_names.contains(&name) {
+            return Ok(());
+        }
+        self.move_file(Path::new(path), name, captured_at)
+    }
+
+    /// 写入归档并记录哈希，原文件在归档完成后删除
+    fn move_file(
+        &mut self,
+        full_path: &Path,
+        name: String,
+        captured_at: Option<u64>,
+    ) -> Result<(), ScreenshotError> {
+        let metadata = fs::metadata(full_path)?;
+        let sha256 = self.append(full_path, &name)?;
+        self.archived.push(full_path.to_path_buf());
+        self.archived_names.insert(name.clone());
+        self.entries
+            .push(ManifestEntry::new(sha256, &metadata, captured_at, name));
+        Ok(())
+    }
+
+    /// 以 `name` 为归档内路径写入文件，返回文件内容的 SHA-256
+    fn append(&mut self, full_path: &Path, name: &str) -> Result<String, ScreenshotError> {
+        let file = File::open(full_path)?;
+        let metadata = file.metadata()?;
+        let modified: DateTime<Utc> = metadata.modified().map(DateTime::from)?;
+        let mut reader = HashingReader {
+            inner: file,
+            hasher: Sha256::new(),
+        };
+
+        match &mut self.writer {
+            ArchiveWriter::Zip(writer) => {
+                let stored = Path::new(name)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| STORED_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+                let mut options = SimpleFileOptions::default()
+                    .compression_method(if stored {
+                        CompressionMethod::Stored
+                    } else {
+                        CompressionMethod::Deflated
+                    })
+                    .large_file(metadata.len() >= u64::from(u32::MAX));
+                if let Ok(time) = zip::DateTime::from_date_and_time(
+                    modified.year() as u16,
+                    modified.month() as u8,
+                    modified.day() as u8,
+                    modified.hour() as u8,
+                    modified.minute() as u8,
+                    modified.second() as u8,
+                ) {
+                    options = options.last_modified_time(time);
+                }
+                writer.start_file(name, options).map_err(Self::zip_error)?;
+                io::copy(&mut reader, writer)?;
+            }
+            ArchiveWriter::TarGz(builder) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(metadata.len());
+                header.set_mode(0o644);
+                header.set_mtime(modified.timestamp().max(0) as u64);
+                builder.append_data(&mut header, name, &mut reader)?;
+            }
+        }
+
+        Ok(format!("{:x}", reader.hasher.finalize()))
+    }
+
+    fn zip_error(e: zip::result::ZipError) -> ScreenshotError {
+        ScreenshotError::Other(format!("写入zip归档失败: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    fn screenshot(dir: &str, name: &str) -> ScreenshotRecord {
+        let path = Path::new(dir).join(name);
+        fs::write(&path, name.as_bytes()).unwrap();
+        serde_json::from_value(serde_json::json!({
+            "timestamp": 1_700_000_000,
+            "target": format!("http://{}", name),
+            "target_type": "url",
+            "success": true,
+            "attempts": 1,
+            "screenshot_path": path.to_string_lossy(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn originals_are_kept_until_the_archive_is_complete() {
+        let dir = output_dir("archive-defer");
+        let mut archive = RunArchive::create(&dir, ArchiveFormat::Zip).unwrap();
+        let record = screenshot(&dir, "a.png");
+        archive.add_screenshot(&record).unwrap();
+
+        // 运行中只有 .partial，原截图仍在
+        let screenshot_path = record.screenshot_path.clone().unwrap();
+        assert!(Path::new(&screenshot_path).exists());
+        assert!(Path::new(&format!("{}.partial", archive.path())).exists());
+        assert!(!Path::new(archive.path()).exists());
+
+        // 模拟中途崩溃：归档未完成，截图不受影响
+        drop(archive);
+        assert_eq!(fs::read(&screenshot_path).unwrap(), b"a.png");
+
+        let log_path = Path::new(&dir).join("log.csv");
+        fs::write(&log_path, b"log").unwrap();
+        let mut archive = RunArchive::create(&dir, ArchiveFormat::Zip).unwrap();
+        archive.add_screenshot(&record).unwrap();
+        let outputs = [log_path.to_string_lossy().to_string()];
+        let archive_path = archive.finish("run", &[record], &outputs).unwrap();
+
+        assert!(!Path::new(&screenshot_path).exists());
+        assert!(!Path::new(&format!("{}.partial", archive_path)).exists());
+        let remaining: Vec<String> = Manifest::list_files(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert!(remaining.is_empty(), "{:?}", remaining);
+
+        let manifest = Manifest::load_from_archive(&archive_path).unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.png", "log.csv"]);
+        assert_eq!(
+            manifest.files[0].captured_at.as_deref(),
+            Some("2023-11-14T22:13:20Z")
+        );
+        assert!(manifest.verify_archive(&archive_path).unwrap().is_intact());
+        fs::remove_file(archive_path).unwrap();
+    }
+
+    #[test]
+    fn existing_archive_is_not_overwritten() {
+        let dir = output_dir("archive-existing");
+        let record = screenshot(&dir, "a.png");
+        let archive_path = RunArchive::create(&dir, ArchiveFormat::TarGz)
+            .unwrap()
+            .finish("run", &[record], &[])
+            .unwrap();
+        let archived = fs::read(&archive_path).unwrap();
+
+        assert!(RunArchive::create(&dir, ArchiveFormat::TarGz).is_err());
+        assert_eq!(fs::read(&archive_path).unwrap(), archived);
+        fs::remove_file(archive_path).unwrap();
+    }
+
+    #[test]
+    fn only_files_of_this_run_are_archived() {
+        let dir = output_dir("archive-scope");
+        let earlier = screenshot(&dir, "earlier.png");
+        fs::create_dir_all(Path::new(&dir).join("other-run")).unwrap();
+        fs::write(Path::new(&dir).join("other-run/b.png"), b"b").unwrap();
+        fs::write(Path::new(&dir).join("urls.txt"), b"http://a").unwrap();
+
+        let mut archive = RunArchive::create(&dir, ArchiveFormat::Zip).unwrap();
+        let record = screenshot(&dir, "a.png");
+        archive.add_screenshot(&record).unwrap();
+        let archive_path = archive.finish("run", &[record], &[]).unwrap();
+
+        let manifest = Manifest::load_from_archive(&archive_path).unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.png"]);
+        let remaining: Vec<String> = Manifest::list_files(&dir)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(remaining, ["earlier.png", "other-run/b.png", "urls.txt"]);
+        assert!(Path::new(earlier.screenshot_path.as_deref().unwrap()).exists());
+        fs::remove_file(archive_path).unwrap();
+    }
+
+    #[test]
+    fn archive_is_placed_outside_the_archived_directory() {
+        let dir = output_dir("archive-location");
+        let current = format!("{}/.", dir);
+        let path = RunArchive::archive_path(&current, ArchiveFormat::Zip);
+        assert_eq!(path, format!("{}.zip", dir));
+        assert!(!RunArchive::is_inside(&current, &path));
+
+        let inner = Path::new(&dir)
+            .join("run.zip")
+            .to_string_lossy()
+            .to_string();
+        assert!(RunArchive::is_inside(&dir, &inner));
+    }
+}
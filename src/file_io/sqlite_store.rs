@@ -0,0 +1,494 @@
+use log::info;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::file_io::file_operations::ScreenshotRecord;
+use crate::models::screenshot_error::ScreenshotError;
+use crate::models::target::Target;
+
+/// 建表语句，多次运行共用同一个数据库
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at INTEGER NOT NULL,
+    finished_at INTEGER,
+    screenshots_dir TEXT NOT NULL,
+    target_count INTEGER NOT NULL,
+    success_count INTEGER,
+    fail_count INTEGER,
+    interrupted INTEGER
+);
+CREATE TABLE IF NOT EXISTS targets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    target TEXT NOT NULL UNIQUE,
+    target_type TEXT NOT NULL,
+    host TEXT NOT NULL,
+    port INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS captures (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    target_id INTEGER NOT NULL REFERENCES targets(id),
+    timestamp INTEGER NOT NULL,
+    success INTEGER NOT NULL,
+    attempts INTEGER NOT NULL,
+    status_code INTEGER,
+    title TEXT,
+    final_url TEXT,
+    screenshot_path TEXT,
+    thumbnail_path TEXT
+);
+CREATE TABLE IF NOT EXISTS headers (
+    capture_id INTEGER NOT NULL REFERENCES captures(id),
+    name TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS redirects (
+    capture_id INTEGER NOT NULL REFERENCES captures(id),
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    status_code INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS technologies (
+    capture_id INTEGER NOT NULL REFERENCES captures(id),
+    name TEXT NOT NULL,
+    version TEXT
+);
+CREATE TABLE IF NOT EXISTS tags (
+    capture_id INTEGER NOT NULL REFERENCES captures(id),
+    tag TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS errors (
+    capture_id INTEGER NOT NULL REFERENCES captures(id),
+    kind TEXT,
+    category TEXT,
+    message TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_captures_run ON captures(run_id);
+CREATE INDEX IF NOT EXISTS idx_captures_status ON captures(status_code);
+CREATE INDEX IF NOT EXISTS idx_headers_capture ON headers(capture_id);
+CREATE INDEX IF NOT EXISTS idx_targets_host ON targets(host);
+CREATE INDEX IF NOT EXISTS idx_technologies_name ON technologies(name);
+CREATE INDEX IF NOT EXISTS idx_tags_tag ON tags(tag);
+";
+
+/// 查询条件，未设置的条件不参与过滤
+#[derive(Debug, Clone, Default)]
+pub struct CaptureQuery {
+    /// 只查询指定运行
+    pub run_id: Option<i64>,
+    /// HTTP状态码
+    pub status_code: Option<u16>,
+    /// 标题包含的文本（不区分大小写）
+    pub title: Option<String>,
+    /// 主机名
+    pub host: Option<String>,
+    /// 识别出的技术名称（不区分大小写）
+    pub technology: Option<String>,
+    /// 页面内容标签，例如 "login"
+    pub tag: Option<String>,
+    /// 只查询失败的截图
+    pub failed_only: bool,
+}
+
+/// 查询结果中的一次截图
+#[derive(Debug, Clone)]
+pub struct CaptureRow {
+    pub run_id: i64,
+    pub timestamp: u64,
+    pub target: String,
+    pub host: String,
+    pub success: bool,
+    pub status_code: Option<u16>,
+    pub title: Option<String>,
+    pub final_url: Option<String>,
+    pub screenshot_path: Option<String>,
+    pub error_kind: Option<String>,
+    pub error_message: Option<String>,
+}
+
+/// 一次运行的概要
+#[derive(Debug, Clone)]
+pub struct RunRow {
+    pub id: i64,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub screenshots_dir: String,
+    pub target_count: usize,
+    pub capture_count: usize,
+    pub success_count: usize,
+    pub interrupted: bool,
+}
+
+/// SQLite 结果数据库
+///
+/// 每次运行写入 `runs` 一行，截图结果随完成随写入，响应头、重定向、技术、标签和错误分表保存。
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    /// 打开（不存在时创建）数据库并建表
+    pub fn open(db_path: &str) -> Result<Self, ScreenshotError> {
+        let connection = Connection::open(db_path)?;
+        connection.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")?;
+        connection.execute_batch(SCHEMA)?;
+        Self::migrate(&connection)?;
+
+        info!("打开结果数据库: {}", db_path);
+        Ok(Self { connection })
+    }
+
+    /// 为旧版本创建的数据库补上新增的列
+    fn migrate(connection: &Connection) -> Result<(), ScreenshotError> {
+        let has_thumbnail: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('captures') WHERE name = 'thumbnail_path'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_thumbnail {
+            connection.execute_batch("ALTER TABLE captures ADD COLUMN thumbnail_path TEXT;")?;
+        }
+        Ok(())
+    }
+
+    /// 登记一次新运行，返回运行ID
+    pub fn begin_run(
+        &self,
+        screenshots_dir: &str,
+        target_count: usize,
+    ) -> Result<i64, ScreenshotError> {
+        self.connection.execute(
+            "INSERT INTO runs (started_at, screenshots_dir, target_count) VALUES (?1, ?2, ?3)",
+            params![Self::now(), screenshots_dir, target_count as i64],
+        )?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    /// 写入一条截图记录
+    pub fn record(
+        &mut self,
+        run_id: i64,
+        record: &ScreenshotRecord,
+    ) -> Result<(), ScreenshotError> {
+        let tx = self.connection.transaction()?;
+
+        let target_id = Self::upsert_target(&tx, record)?;
+        tx.execute(
+            "INSERT INTO captures (run_id, target_id, timestamp, success, attempts, status_code, title, final_url, screenshot_path, thumbnail_path)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                run_id,
+                target_id,
+                record.timestamp as i64,
+                record.success,
+                record.attempts,
+                record.status_code,
+                record.title,
+                record.final_url,
+                record.screenshot_path,
+                record.thumbnail_path,
+            ],
+        )?;
+        let capture_id = tx.last_insert_rowid();
+
+        for (name, value) in &record.headers {
+            tx.execute(
+                "INSERT INTO headers (capture_id, name, value) VALUES (?1, ?2, ?3)",
+                params![capture_id, name, value],
+            )?;
+        }
+        for (position, hop) in record.redirect_chain.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO redirects (capture_id, position, url, status_code) VALUES (?1, ?2, ?3, ?4)",
+                params![capture_id, position as i64, hop.url, hop.status_code],
+            )?;
+        }
+        for technology in &record.technologies {
+            tx.execute(
+                "INSERT INTO technologies (capture_id, name, version) VALUES (?1, ?2, ?3)",
+                params![capture_id, technology.name, technology.version],
+            )?;
+        }
+        for tag in &record.tags {
+            tx.execute(
+                "INSERT INTO tags (capture_id, tag) VALUES (?1, ?2)",
+                params![capture_id, tag],
+            )?;
+        }
+        if !record.success {
+            tx.execute(
+                "INSERT INTO errors (capture_id, kind, category, message) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    capture_id,
+                    record.error_kind,
+                    record.error_category,
+                    record.error_message,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 记录运行结束时的统计
+    pub fn finish_run(
+        &self,
+        run_id: i64,
+        success_count: usize,
+        fail_count: usize,
+        interrupted: bool,
+    ) -> Result<(), ScreenshotError> {
+        self.connection.execute(
+            "UPDATE runs SET finished_at = ?1, success_count = ?2, fail_count = ?3, interrupted = ?4 WHERE id = ?5",
+            params![
+                Self::now(),
+                success_count as i64,
+                fail_count as i64,
+                interrupted,
+                run_id
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 按条件查询截图记录，按运行和时间排序
+    pub fn query_captures(&self, query: &CaptureQuery) -> Result<Vec<CaptureRow>, ScreenshotError> {
+        let mut statement = self.connection.prepare(
+            "SELECT c.run_id, c.timestamp, t.target, t.host, c.success, c.status_code, c.title,
+                    c.final_url, c.screenshot_path, e.kind, e.message
+             FROM captures c
+             JOIN targets t ON t.id = c.target_id
+             LEFT JOIN errors e ON e.capture_id = c.id
+             WHERE (?1 IS NULL OR c.run_id = ?1)
+               AND (?2 IS NULL OR c.status_code = ?2)
+               AND (?3 IS NULL OR c.title LIKE '%' || ?3 || '%')
+               AND (?4 IS NULL OR t.host = lower(?4))
+               AND (?5 = 0 OR c.success = 0)
+               AND (?6 IS NULL OR EXISTS (
+                    SELECT 1 FROM technologies g
+                    WHERE g.capture_id = c.id AND g.name = ?6 COLLATE NOCASE))
+               AND (?7 IS NULL OR EXISTS (
+                    SELECT 1 FROM tags a WHERE a.capture_id = c.id AND a.tag = ?7))
+             ORDER BY c.run_id, c.timestamp, c.id",
+        )?;
+
+        let rows = statement.query_map(
+            params![
+                query.run_id,
+                query.status_code,
+                query.title,
+                query.host,
+                query.failed_only,
+                query.technology,
+                query.tag,
+            ],
+            |row| {
+                Ok(CaptureRow {
+                    run_id: row.get(0)?,
+                    timestamp: row.get::<_, i64>(1)? as u64,
+                    target: row.get(2)?,
+                    host: row.get(3)?,
+                    success: row.get(4)?,
+                    status_code: row.get(5)?,
+                    title: row.get(6)?,
+                    final_url: row.get(7)?,
+                    screenshot_path: row.get(8)?,
+                    error_kind: row.get(9)?,
+                    error_message: row.get(10)?,
+                })
+            },
+        )?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// 列出所有运行
+    pub fn list_runs(&self) -> Result<Vec<RunRow>, ScreenshotError> {
+        let mut statement = self.connection.prepare(
+            "SELECT r.id, r.started_at, r.finished_at, r.screenshots_dir, r.target_count,
+                    COUNT(c.id), COALESCE(SUM(c.success), 0), COALESCE(r.interrupted, 0)
+             FROM runs r
+             LEFT JOIN captures c ON c.run_id = r.id
+             GROUP BY r.id
+             ORDER BY r.id",
+        )?;
+
+        let rows = statement.query_map([], |row| {
+            Ok(RunRow {
+                id: row.get(0)?,
+                started_at: row.get::<_, i64>(1)? as u64,
+                finished_at: row.get::<_, Option<i64>>(2)?.map(|t| t as u64),
+                screenshots_dir: row.get(3)?,
+                target_count: row.get::<_, i64>(4)? as usize,
+                capture_count: row.get::<_, i64>(5)? as usize,
+                success_count: row.get::<_, i64>(6)? as usize,
+                interrupted: row.get(7)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// 查找目标ID，不存在时插入
+    fn upsert_target(
+        connection: &Connection,
+        record: &ScreenshotRecord,
+    ) -> Result<i64, ScreenshotError> {
+        let existing = connection
+            .query_row(
+                "SELECT id FROM targets WHERE target = ?1",
+                params![record.target],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = existing {
+            return Ok(id);
+        }
+
+        let (host, port) = Target::new(record.target.clone())
+            .map(|target| target.get_host_and_port())
+            .unwrap_or_else(|| (record.target.to_ascii_lowercase(), 80));
+        connection.execute(
+            "INSERT INTO targets (target, target_type, host, port) VALUES (?1, ?2, ?3, ?4)",
+            params![record.target, record.target_type, host, port],
+        )?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(value: serde_json::Value) -> ScreenshotRecord {
+        let mut base = json!({
+            "timestamp": 1_700_000_000,
+            "target_type": "url",
+            "attempts": 1,
+        });
+        base.as_object_mut()
+            .unwrap()
+            .extend(value.as_object().unwrap().clone());
+        serde_json::from_value(base).unwrap()
+    }
+
+    fn targets(rows: &[CaptureRow]) -> Vec<&str> {
+        rows.iter().map(|row| row.target.as_str()).collect()
+    }
+
+    #[test]
+    fn records_round_trip_and_filter() {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-sqlite-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("results.db").to_string_lossy().to_string();
+
+        let mut store = SqliteStore::open(&db_path).unwrap();
+        let first = store.begin_run("shots", 3).unwrap();
+        store
+            .record(
+                first,
+                &record(json!({
+                    "target": "https://Admin.Example.com/login",
+                    "success": true,
+                    "status_code": 200,
+                    "title": "Admin Login",
+                    "screenshot_path": "shots/admin.png",
+                    "technologies": [{ "name": "nginx", "version": "1.25.3" }],
+                    "tags": ["login"],
+                })),
+            )
+            .unwrap();
+        store
+            .record(
+                first,
+                &record(json!({
+                    "target": "http://blog.example.com",
+                    "success": true,
+                    "status_code": 404,
+                    "title": "Not Found",
+                })),
+            )
+            .unwrap();
+        store
+            .record(
+                first,
+                &record(json!({
+                    "target": "http://down.example.com",
+                    "success": false,
+                    "error_kind": "dns",
+                    "error_message": "域名解析失败",
+                })),
+            )
+            .unwrap();
+        store.finish_run(first, 2, 1, false).unwrap();
+        drop(store);
+
+        // 重新打开后数据仍在，同一目标在第二次运行中复用
+        let mut store = SqliteStore::open(&db_path).unwrap();
+        let second = store.begin_run("shots", 1).unwrap();
+        store
+            .record(
+                second,
+                &record(json!({
+                    "target": "http://blog.example.com",
+                    "success": true,
+                    "status_code": 200,
+                })),
+            )
+            .unwrap();
+
+        let all = store.query_captures(&CaptureQuery::default()).unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].host, "admin.example.com");
+        assert_eq!(all[0].screenshot_path.as_deref(), Some("shots/admin.png"));
+
+        let query = |query: CaptureQuery| store.query_captures(&query).unwrap();
+        let by_host = query(CaptureQuery {
+            host: Some("BLOG.example.com".to_string()),
+            ..CaptureQuery::default()
+        });
+        assert_eq!(
+            by_host.iter().map(|row| row.run_id).collect::<Vec<_>>(),
+            [first, second]
+        );
+        let in_first_run = query(CaptureQuery {
+            run_id: Some(first),
+            status_code: Some(404),
+            ..CaptureQuery::default()
+        });
+        assert_eq!(targets(&in_first_run), ["http://blog.example.com"]);
+        let by_title_and_tech = query(CaptureQuery {
+            title: Some("login".to_string()),
+            technology: Some("NGINX".to_string()),
+            tag: Some("login".to_string()),
+            ..CaptureQuery::default()
+        });
+        assert_eq!(
+            targets(&by_title_and_tech),
+            ["https://Admin.Example.com/login"]
+        );
+        let failed = query(CaptureQuery {
+            failed_only: true,
+            ..CaptureQuery::default()
+        });
+        assert_eq!(targets(&failed), ["http://down.example.com"]);
+        assert_eq!(failed[0].error_kind.as_deref(), Some("dns"));
+
+        let runs = store.list_runs().unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[0].capture_count, runs[0].success_count), (3, 2));
+        assert!(runs[0].finished_at.is_some());
+        assert!(runs[1].finished_at.is_none());
+    }
+}
@@ -2,16 +2,20 @@
 //!
 //! 按照关注点分离原则设计的模块化网页截图工具
 
+pub mod analysis;
 pub mod cli;
 pub mod config;
 pub mod file_io;
 pub mod models;
+pub mod report;
 pub mod screenshot;
 pub mod utils;
 
 pub use cli::cli_parser::CliParser;
 pub use config::app_config::AppConfig;
 pub use file_io::file_operations::FileOperations;
+pub use file_io::result_sink::ResultSink;
+pub use models::screenshot_error::ScreenshotError;
 pub use models::target::{ScreenshotResult, Target, TargetType};
 pub use screenshot::{
     async_screenshot_service::AsyncScreenshotService, concurrent_executor::ConcurrentExecutor,
@@ -1,14 +1,28 @@
-use picmyweb2::cli::cli_parser::CliParser;
-use picmyweb2::config::app_config::AppConfig;
-use picmyweb2::file_io::file_operations::{FileOperations, ScreenshotRecord};
+use picmyweb2::analysis::clustering::ScreenshotClusters;
+use picmyweb2::analysis::run_diff::{RunDiff, RunLog};
+use picmyweb2::cli::cli_parser::{
+    CliCommand, CliParser, DiffOptions, QueryOptions, ReportFormat, ReportOptions, VerifyOptions,
+};
+use picmyweb2::file_io::file_operations::{CSV_LOG_FILENAME, FileOperations, JSONL_LOG_FILENAME};
+use picmyweb2::file_io::manifest::{MANIFEST_FILENAME, Manifest};
+use picmyweb2::file_io::result_sink::{
+    ArchiveSink, CsvSink, JsonlSink, MultiSink, SqliteSink, StdoutSink,
+};
+use picmyweb2::file_io::run_archive::RunArchive;
+use picmyweb2::file_io::sqlite_store::SqliteStore;
 use picmyweb2::models::target::{Target, TargetType};
+use picmyweb2::report::diff_report::{DIFF_REPORT_FILENAME, DiffReport};
+use picmyweb2::report::html_report::{HTML_REPORT_FILENAME, HtmlReport};
+use picmyweb2::report::markdown_report::{MARKDOWN_REPORT_FILENAME, MarkdownReport};
+use picmyweb2::report::text_report::{TEXT_REPORT_FILENAME, TextReport};
 use picmyweb2::screenshot::concurrent_executor::ConcurrentExecutor;
+use picmyweb2::screenshot::shutdown_signal::ShutdownSignal;
+use picmyweb2::utils::path_utils::PathUtils;
 
-use csv::Writer;
 use log::{error, info, warn};
 use std::fs;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use tokio;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -18,8 +32,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("picmyweb2 应用程序启动");
 
     // 解析命令行参数
-    let config = CliParser::parse();
-    let file_path = CliParser::get_file_path();
+    let (config, file_path) = match CliParser::parse() {
+        CliCommand::Capture { config, file_path } => (config, file_path),
+        CliCommand::Query(options) => return run_query(&options),
+        CliCommand::Report(options) => return run_report(&options),
+        CliCommand::Diff(options) => return run_diff(&options),
+        CliCommand::Verify(options) => return run_verify(&options),
+    };
 
     info!("正在读取文件: {}", file_path);
 
@@ -29,8 +48,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    // 提前加载指纹规则、地址栏模板和命名模板，有误时在启动浏览器之前退出
+    let executor = match ConcurrentExecutor::new(config.clone()) {
+        Ok(executor) => executor,
+        Err(e) => {
+            error!("{}", e);
+            return Ok(());
+        }
+    };
+
     // 解析目标
-    let targets = FileOperations::parse_targets_from_file(&file_path)?;
+    let mut targets = FileOperations::parse_targets_from_file(&file_path)?;
 
     if targets.is_empty() {
         warn!("未找到有效的URL目标");
@@ -43,29 +71,387 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 确保截图目录存在
     FileOperations::ensure_screenshots_dir(&config.screenshots_dir)?;
 
-    // 创建CSV日志文件
-    let csv_path = format!("{}\\screenshot_log.csv", config.screenshots_dir);
-    let csv_writer = FileOperations::create_csv_log_file(&csv_path)?;
-    let csv_writer_arc = Arc::new(Mutex::new(csv_writer));
+    let output_dir = Path::new(&config.screenshots_dir);
+    let csv_path = output_dir
+        .join(CSV_LOG_FILENAME)
+        .to_string_lossy()
+        .to_string();
+
+    // 旧版本在非 Windows 系统上把日志写在输出目录旁的 `<目录>\screenshot_log.csv`，续跑时移回目录中
+    let legacy_csv_path = format!("{}\\{}", config.screenshots_dir, CSV_LOG_FILENAME);
+    if config.resume
+        && !FileOperations::file_exists(&csv_path)
+        && FileOperations::file_exists(&legacy_csv_path)
+    {
+        fs::rename(&legacy_csv_path, &csv_path)?;
+        info!("已将旧位置的日志移入输出目录: {}", csv_path);
+    }
+
+    // 续跑时跳过日志中已记录的目标
+    if config.resume && FileOperations::file_exists(&csv_path) {
+        let records = FileOperations::read_csv_log(&csv_path)?;
+        let completed = FileOperations::completed_targets(&records, config.retry_failed);
+        let total_count = targets.len();
+        targets.retain(|target| !completed.contains(&target.original_text));
+        info!(
+            "续跑模式: 跳过 {} 个已记录的目标，剩余 {} 个",
+            total_count - targets.len(),
+            targets.len()
+        );
+
+        if targets.is_empty() {
+            println!("所有目标均已在日志中记录，无需续跑: {}", csv_path);
+            return Ok(());
+        }
+    }
+
+    // 组装结果输出：CSV日志始终写入，续跑时追加
+    let jsonl_path = output_dir
+        .join(JSONL_LOG_FILENAME)
+        .to_string_lossy()
+        .to_string();
+    let mut sinks = MultiSink::new().with_sink(CsvSink::new(&csv_path, config.resume));
+    if config.jsonl_log {
+        sinks.push(Box::new(JsonlSink::new(&jsonl_path, config.resume)));
+    }
+    if let Some(db_path) = &config.sqlite_path {
+        sinks.push(Box::new(SqliteSink::new(
+            db_path,
+            &config.screenshots_dir,
+            targets.len(),
+        )));
+    }
+    if config.stdout_log {
+        sinks.push(Box::new(StdoutSink::new()));
+    }
+    // 截图边产生边写入归档，最后一个添加，确保其他输出先记录完整路径
+    let archive = match config.archive {
+        Some(format) => {
+            let archive = Arc::new(Mutex::new(RunArchive::create(
+                &config.screenshots_dir,
+                format,
+            )?));
+            sinks.push(Box::new(ArchiveSink::new(Arc::clone(&archive))));
+            Some(archive)
+        }
+        None => None,
+    };
+
+    // 监听中断信号
+    let shutdown = ShutdownSignal::new();
+    spawn_shutdown_listener(shutdown.clone());
 
     // 开始异步截图会话
-    let (success_count, fail_count) =
-        start_async_screenshot_session(&targets, config.clone(), csv_writer_arc).await?;
+    info!(
+        "开始异步截图，运行ID: {}, 目标数量: {}, 并发数: {}",
+        config.run_id,
+        targets.len(),
+        config.concurrency
+    );
+    let executor = executor.with_shutdown_signal(shutdown);
+    let summary = match executor.execute_with_sink(targets.clone(), sinks).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            error!("无法打开结果输出，未执行截图: {}", e);
+            if let Some(archive) =
+                archive.and_then(|archive| Arc::into_inner(archive)?.into_inner().ok())
+            {
+                archive.discard();
+            }
+            return Ok(());
+        }
+    };
 
-    // 输出最终结果信息
-    let completion_message = format!(
+    // 输出最终结果信息，结果输出到标准输出时改用标准错误，避免混入结果数据
+    let print = |message: String| {
+        if config.stdout_log {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    };
+    let completed_count = summary.success_count + summary.fail_count;
+    if summary.interrupted {
+        print(format!(
+            "截图已中断，已完成 {}/{} 个目标",
+            completed_count,
+            targets.len()
+        ));
+    }
+    let success_rate = if completed_count > 0 {
+        (summary.success_count as f64 / completed_count as f64) * 100.0
+    } else {
+        0.0
+    };
+    print(format!(
         "异步截图完成! 成功: {}, 失败: {}，成功率：{:.2}%",
-        success_count,
-        fail_count,
-        (success_count as f64 / (success_count + fail_count) as f64) * 100.0
+        summary.success_count, summary.fail_count, success_rate
+    ));
+    print(format!("截图保存在: {}", config.screenshots_dir));
+    print(format!("CSV日志文件: {}", csv_path));
+    if config.jsonl_log {
+        print(format!("JSONL日志文件: {}", jsonl_path));
+    }
+    if let Some(db_path) = &config.sqlite_path {
+        print(format!("结果数据库: {}", db_path));
+    }
+
+    // 对完整日志中的截图聚类并写回CSV，再据此生成HTML报告，续跑时也包含之前的记录
+    let mut records = Vec::new();
+    let mut outputs = vec![csv_path.clone()];
+    if config.jsonl_log {
+        outputs.push(jsonl_path.clone());
+    }
+    match FileOperations::read_csv_log(&csv_path).and_then(|log| {
+        records = log;
+        ScreenshotClusters::assign(&mut records, config.cluster_threshold);
+        FileOperations::rewrite_csv_log(&csv_path, &records)?;
+        HtmlReport::generate(&records, &config.screenshots_dir)
+    }) {
+        Ok(report_path) => {
+            print(format!("HTML报告: {}", report_path));
+            outputs.push(report_path);
+        }
+        Err(e) => error!("更新日志聚类或生成HTML报告失败: {}", e),
+    }
+
+    // 所有输出写完后生成完整性清单，截图时间取自日志；打包时清单随剩余文件一起放入归档
+    let archive = archive.and_then(|archive| Arc::into_inner(archive)?.into_inner().ok());
+    let finished = match archive {
+        Some(archive) => archive
+            .finish(&config.run_id, &records, &outputs)
+            .map(|archive_path| format!("运行归档: {}", archive_path)),
+        None => Manifest::build(&config.screenshots_dir, &config.run_id, &records)
+            .and_then(|manifest| manifest.write(&config.screenshots_dir))
+            .map(|manifest_path| format!("完整性清单: {}", manifest_path)),
+    };
+    match finished {
+        Ok(message) => print(message),
+        Err(e) => error!("生成完整性清单或运行归档失败: {}", e),
+    }
+    print("应用程序正常退出".to_string());
+    Ok(())
+}
+
+/// 执行 `query` 子命令
+fn run_query(options: &QueryOptions) -> Result<(), Box<dyn std::error::Error>> {
+    if !FileOperations::file_exists(&options.db_path) {
+        error!("数据库不存在: {}", options.db_path);
+        return Ok(());
+    }
+    let store = SqliteStore::open(&options.db_path)?;
+
+    if options.list_runs {
+        for run in store.list_runs()? {
+            println!(
+                "#{}\t开始: {}\t结束: {}\t目标: {}\t已完成: {}\t成功: {}\t{}{}",
+                run.id,
+                run.started_at,
+                run.finished_at
+                    .map_or_else(|| "-".to_string(), |t| t.to_string()),
+                run.target_count,
+                run.capture_count,
+                run.success_count,
+                run.screenshots_dir,
+                if run.interrupted { "\t(已中断)" } else { "" }
+            );
+        }
+        return Ok(());
+    }
+
+    let rows = store.query_captures(&options.query)?;
+    if options.hosts_only {
+        let hosts: std::collections::BTreeSet<_> = rows.iter().map(|row| &row.host).collect();
+        for host in &hosts {
+            println!("{}", host);
+        }
+        info!("共 {} 个主机", hosts.len());
+        return Ok(());
+    }
+
+    for row in &rows {
+        let detail = if row.success {
+            row.title.clone().unwrap_or_default()
+        } else {
+            format!(
+                "[{}] {}",
+                row.error_kind.as_deref().unwrap_or("unknown"),
+                row.error_message.as_deref().unwrap_or_default()
+            )
+        };
+        println!(
+            "#{}\t{}\t{}\t{}",
+            row.run_id,
+            row.status_code
+                .map_or_else(|| "-".to_string(), |code| code.to_string()),
+            row.target,
+            detail
+        );
+    }
+    info!("共 {} 条记录", rows.len());
+    Ok(())
+}
+
+/// 执行 `report` 子命令
+fn run_report(options: &ReportOptions) -> Result<(), Box<dyn std::error::Error>> {
+    if !FileOperations::file_exists(&options.input_path) {
+        error!("日志文件不存在: {}", options.input_path);
+        return Ok(());
+    }
+    let mut records = FileOperations::read_result_log(&options.input_path)?;
+    ScreenshotClusters::assign(&mut records, options.cluster_threshold);
+    if options.hide_noise {
+        records.retain(|record| !record.is_noise());
+    }
+    if options.only_interesting {
+        records.retain(|record| record.is_interesting());
+    }
+
+    let output_path = options.output_path.clone().unwrap_or_else(|| {
+        let filename = match options.format {
+            ReportFormat::Markdown => MARKDOWN_REPORT_FILENAME,
+            ReportFormat::Text => TEXT_REPORT_FILENAME,
+            ReportFormat::Html => HTML_REPORT_FILENAME,
+        };
+        Path::new(&PathUtils::parent_dir(&options.input_path))
+            .join(filename)
+            .to_string_lossy()
+            .to_string()
+    });
+
+    // 截图链接相对于报告所在目录
+    let base_dir = PathUtils::parent_dir(&output_path);
+    let content = match options.format {
+        ReportFormat::Markdown => MarkdownReport::render(&records, &base_dir),
+        ReportFormat::Text => TextReport::render(&records, &base_dir),
+        ReportFormat::Html => {
+            HtmlReport::render(&FileOperations::latest_records(&records), &base_dir)?
+        }
+    };
+    fs::write(&output_path, content)?;
+
+    println!("报告已生成: {}", output_path);
+    Ok(())
+}
+
+/// 执行 `diff` 子命令
+fn run_diff(options: &DiffOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let old = RunLog::load(&options.old_path)?;
+    let new = RunLog::load(&options.new_path)?;
+    let diff = RunDiff::compare(&old, &new, &options.thresholds, &options.output_dir)?;
+
+    for record in &diff.added {
+        println!("+ {}", record.target);
+    }
+    for record in &diff.removed {
+        println!("- {}", record.target);
+    }
+    for changed in &diff.changed {
+        println!("~ {}\t{}", changed.target, changed.reasons.join("; "));
+    }
+
+    let report_path = Path::new(&options.output_dir).join(DIFF_REPORT_FILENAME);
+    fs::write(&report_path, DiffReport::render(&diff, &options.output_dir))?;
+
+    println!(
+        "新增: {}，移除: {}，变化: {}，未变化: {}",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len(),
+        diff.unchanged_count
     );
-    println!("{}", completion_message);
-    println!("截图保存在: {}", config.screenshots_dir);
-    println!("CSV日志文件: {}", csv_path);
-    println!("应用程序正常退出");
+    println!("对比报告: {}", report_path.display());
     Ok(())
 }
 
+/// 执行 `verify` 子命令
+fn run_verify(options: &VerifyOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = match &options.manifest_path {
+        Some(manifest_path) => Manifest::load(manifest_path)?,
+        None if Path::new(&options.dir).is_file() => Manifest::load_from_archive(&options.dir)?,
+        None => Manifest::load(
+            &Path::new(&options.dir)
+                .join(MANIFEST_FILENAME)
+                .to_string_lossy(),
+        )?,
+    };
+
+    let mut check = if Path::new(&options.dir).is_file() {
+        manifest.verify_archive(&options.dir)?
+    } else {
+        manifest.verify(&options.dir)?
+    };
+    // 打包后的输出目录只剩清单，文件都在目录旁的归档中
+    if check.verified == 0
+        && check.tampered.is_empty()
+        && !check.missing.is_empty()
+        && let Some(archive_path) = RunArchive::find(&options.dir)
+    {
+        println!("输出目录已打包，改为校验归档: {}", archive_path);
+        check = manifest.verify_archive(&archive_path)?;
+    }
+
+    for path in &check.tampered {
+        println!("已改动\t{}", path);
+    }
+    for path in &check.missing {
+        println!("缺失\t{}", path);
+    }
+    for path in &check.untracked {
+        println!("未登记\t{}", path);
+    }
+    println!(
+        "运行 {}（{} {}，{}）：一致 {}，已改动 {}，缺失 {}，未登记 {}",
+        manifest.run_id,
+        manifest.tool,
+        manifest.tool_version,
+        manifest.created_at,
+        check.verified,
+        check.tampered.len(),
+        check.missing.len(),
+        check.untracked.len()
+    );
+
+    if !check.is_intact() {
+        return Err(format!("{} 与清单不一致", options.dir).into());
+    }
+    Ok(())
+}
+
+/// 在后台监听 Ctrl-C / SIGTERM，收到后触发停止信号
+fn spawn_shutdown_listener(shutdown: ShutdownSignal) {
+    tokio::spawn(async move {
+        wait_for_termination().await;
+        warn!("收到中断信号，停止调度新目标，等待进行中的截图完成...");
+        shutdown.trigger();
+    });
+}
+
+/// 等待终止信号
+#[cfg(unix)]
+async fn wait_for_termination() {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = sigterm.recv() => {}
+            }
+        }
+        Err(e) => {
+            error!("注册SIGTERM处理失败: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+/// 等待终止信号
+#[cfg(not(unix))]
+async fn wait_for_termination() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
 /// 显示目标统计信息
 fn display_target_statistics(targets: &[Target]) {
     info!("找到 {} 个目标", targets.len());
@@ -90,92 +476,3 @@ fn display_target_statistics(targets: &[Target]) {
     info!("  - IP地址: {}", ip_count);
     info!("  - IP:端口: {}", ip_port_count);
 }
-
-/// 开始异步截图会话
-async fn start_async_screenshot_session(
-    targets: &[Target],
-    config: AppConfig,
-    csv_writer_arc: Arc<Mutex<Writer<fs::File>>>,
-) -> Result<(usize, usize), Box<dyn std::error::Error>> {
-    let start_message = format!(
-        "开始异步截图，目标数量: {}, 并发数: {}",
-        targets.len(),
-        config.concurrency
-    );
-    info!("{}", start_message);
-
-    info!("使用多线程异步模式进行截图...");
-
-    // 创建并发执行器
-    let executor = ConcurrentExecutor::new(config.clone());
-
-    // 执行并发截图，实时处理每个结果
-    let csv_writer_arc_clone = Arc::clone(&csv_writer_arc);
-
-    let (success_count, fail_count) = executor
-        .execute_concurrent_screenshots(targets.to_vec(), move |target, result| {
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-
-            // 创建截图记录
-            let mut record = ScreenshotRecord {
-                timestamp,
-                target: target.original_text.clone(),
-                target_type: target.get_type().to_string(),
-                success: false,
-                error_message: None,
-                screenshot_path: None,
-            };
-
-            // 实时处理每个截图结果
-            match result {
-                Ok(screenshot_result) => {
-                    if screenshot_result.success {
-                        record.success = true;
-                        record.screenshot_path = screenshot_result.file_path.clone();
-
-                        let success_log = format!("✓ 成功截图: {}", target.original_text);
-                        info!("{}", success_log);
-
-                        // 写入CSV记录
-                        if let Ok(mut csv_writer) = csv_writer_arc_clone.lock() {
-                            let _ = FileOperations::log_csv_record(&mut *csv_writer, &record);
-                        }
-                    } else {
-                        record.success = false;
-                        record.error_message = screenshot_result.error_message.clone();
-
-                        let error_log = format!(
-                            "✗ 截图失败 {}: {}",
-                            target.original_text,
-                            screenshot_result
-                                .error_message
-                                .as_ref()
-                                .unwrap_or(&"未知错误".to_string())
-                        );
-                        error!("{}", error_log);
-
-                        if let Ok(mut csv_writer) = csv_writer_arc_clone.lock() {
-                            let _ = FileOperations::log_csv_record(&mut *csv_writer, &record);
-                        }
-                    }
-                }
-                Err(e) => {
-                    record.success = false;
-                    record.error_message = Some(e.to_string());
-
-                    let error_log = format!("✗ 截图失败 {}: {}", target.original_text, e);
-                    error!("{}", error_log);
-
-                    if let Ok(mut csv_writer) = csv_writer_arc_clone.lock() {
-                        let _ = FileOperations::log_csv_record(&mut *csv_writer, &record);
-                    }
-                }
-            }
-        })
-        .await;
-
-    Ok((success_count, fail_count))
-}
@@ -0,0 +1,42 @@
+use std::fmt;
+
+/// 页面内容标签，标出审阅时值得优先关注的页面
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ContentTag {
+    /// 含密码输入框或登录标题的登录入口
+    Login,
+    /// 已知的管理后台
+    AdminPanel,
+    /// Web 服务器的目录列表
+    DirectoryListing,
+    /// 无需登录即可访问的监控、运维仪表盘
+    Dashboard,
+    /// 页面上出现默认账号密码的提示
+    DefaultCredentials,
+}
+
+impl ContentTag {
+    /// 解析日志中保存的标签名称
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "login" => Some(ContentTag::Login),
+            "admin_panel" => Some(ContentTag::AdminPanel),
+            "directory_listing" => Some(ContentTag::DirectoryListing),
+            "dashboard" => Some(ContentTag::Dashboard),
+            "default_credentials" => Some(ContentTag::DefaultCredentials),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ContentTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentTag::Login => write!(f, "login"),
+            ContentTag::AdminPanel => write!(f, "admin_panel"),
+            ContentTag::DirectoryListing => write!(f, "directory_listing"),
+            ContentTag::Dashboard => write!(f, "dashboard"),
+            ContentTag::DefaultCredentials => write!(f, "default_credentials"),
+        }
+    }
+}
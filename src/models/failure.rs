@@ -0,0 +1,103 @@
+use std::fmt;
+
+/// 截图失败分类
+///
+/// 用于决定失败是否值得重试，并写入日志便于事后按类别筛选。
+/// 由 [`ScreenshotError::category`](crate::models::screenshot_error::ScreenshotError::category) 按错误类型得出。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureCategory {
+    /// 浏览器启动失败或运行中崩溃、连接断开
+    BrowserCrash,
+    /// 导航或等待页面事件超时
+    NavigationTimeout,
+    /// 连接被重置、中断或临时性的网络故障
+    ConnectionReset,
+    /// 域名无法解析，重试也不会成功
+    Dns,
+    /// 其他不可重试的错误
+    Other,
+}
+
+impl FailureCategory {
+    /// 该类失败是否可以重试
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, FailureCategory::Dns | FailureCategory::Other)
+    }
+}
+
+impl fmt::Display for FailureCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureCategory::BrowserCrash => write!(f, "browser_crash"),
+            FailureCategory::NavigationTimeout => write!(f, "navigation_timeout"),
+            FailureCategory::ConnectionReset => write!(f, "connection_reset"),
+            FailureCategory::Dns => write!(f, "dns"),
+            FailureCategory::Other => write!(f, "other"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::screenshot_error::ScreenshotError;
+
+    #[test]
+    fn classifies_chrome_error_codes() {
+        let cases = [
+            ("Target closed", FailureCategory::BrowserCrash),
+            (
+                "net::ERR_CONNECTION_TIMED_OUT",
+                FailureCategory::NavigationTimeout,
+            ),
+            (
+                "net::ERR_CONNECTION_RESET",
+                FailureCategory::ConnectionReset,
+            ),
+            ("net::ERR_NAME_NOT_RESOLVED", FailureCategory::Dns),
+            ("net::ERR_NAME_RESOLUTION_FAILED", FailureCategory::Dns),
+            ("net::ERR_CERT_AUTHORITY_INVALID", FailureCategory::Other),
+        ];
+        for (message, category) in cases {
+            let error = ScreenshotError::from_browser_message(message.to_string());
+            assert_eq!(error.category(), category, "{}", message);
+        }
+    }
+
+    #[test]
+    fn dns_failures_are_not_retried() {
+        assert!(!FailureCategory::Dns.is_retryable());
+        assert!(!FailureCategory::Other.is_retryable());
+        assert!(FailureCategory::ConnectionReset.is_retryable());
+
+        for message in [
+            "Navigate failed: net::ERR_NAME_NOT_RESOLVED",
+            "Navigate failed: net::ERR_NAME_RESOLUTION_FAILED",
+        ] {
+            let error = ScreenshotError::from_browser_message(message.to_string());
+            assert_eq!(error.kind(), "dns");
+            assert_eq!(error.category(), FailureCategory::Dns);
+            assert!(!error.is_retryable());
+        }
+    }
+
+    #[test]
+    fn transient_errors_are_retried() {
+        for message in [
+            "Navigate failed: net::ERR_CONNECTION_RESET",
+            "Navigate failed: net::ERR_TIMED_OUT",
+            "Unable to make method calls because underlying connection is closed",
+            "no available ports",
+        ] {
+            let error = ScreenshotError::from_browser_message(message.to_string());
+            assert!(error.is_retryable(), "{}", message);
+        }
+        for message in [
+            "Navigate failed: net::ERR_CONNECTION_REFUSED",
+            "Could not auto detect a chrome executable",
+        ] {
+            let error = ScreenshotError::from_browser_message(message.to_string());
+            assert!(!error.is_retryable(), "{}", message);
+        }
+    }
+}
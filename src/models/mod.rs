@@ -2,4 +2,11 @@
 //!
 //! 包含应用程序的核心数据结构和枚举
 
+pub mod content_tag;
+pub mod failure;
+pub mod page_class;
+pub mod page_content;
+pub mod page_response;
+pub mod screenshot_error;
 pub mod target;
+pub mod technology;
@@ -0,0 +1,45 @@
+use std::fmt;
+
+/// 截图成功后的页面分类
+///
+/// 空白页、浏览器错误页和服务器默认页虽然截图成功，但通常没有审阅价值
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageClass {
+    /// 有实际内容的页面
+    Normal,
+    /// 几乎没有内容的空白页
+    Blank,
+    /// Chrome 内置的网络错误页
+    ErrorPage,
+    /// Web 服务器默认页、停放域名等占位页面
+    DefaultPage,
+}
+
+impl PageClass {
+    /// 是否属于报告中可以隐藏的噪音页面
+    pub fn is_noise(&self) -> bool {
+        !matches!(self, PageClass::Normal)
+    }
+
+    /// 解析日志中保存的分类名称
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(PageClass::Normal),
+            "blank" => Some(PageClass::Blank),
+            "error_page" => Some(PageClass::ErrorPage),
+            "default_page" => Some(PageClass::DefaultPage),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PageClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageClass::Normal => write!(f, "normal"),
+            PageClass::Blank => write!(f, "blank"),
+            PageClass::ErrorPage => write!(f, "error_page"),
+            PageClass::DefaultPage => write!(f, "default_page"),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use std::collections::BTreeMap;
+
+/// 截图前从页面 DOM 读取的内容
+#[derive(Debug, Clone, Default)]
+pub struct PageContent {
+    /// 页面当前的地址
+    pub url: String,
+    /// 页面可见文本，截断到固定长度
+    pub text: String,
+    /// 是否为 Chrome 内置的网络错误页
+    pub chrome_error: bool,
+    /// 页面 HTML，截断到固定长度
+    pub html: String,
+    /// 外部脚本地址
+    pub script_srcs: Vec<String>,
+    /// `<meta name=...>` 标签，键名统一为小写
+    pub meta: BTreeMap<String, String>,
+    /// 页面可见的 Cookie
+    pub cookies: BTreeMap<String, String>,
+    /// 密码输入框数量
+    pub password_fields: usize,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 重定向链中的一跳
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedirectHop {
+    /// 返回重定向的URL
+    pub url: String,
+    /// 重定向状态码
+    pub status_code: u16,
+}
+
+/// 主文档的响应信息
+#[derive(Debug, Clone, Default)]
+pub struct PageResponse {
+    /// 最终文档的HTTP状态码
+    pub status_code: Option<u16>,
+    /// 重定向后的最终URL
+    pub final_url: Option<String>,
+    /// 最终文档的响应头，键名统一为小写
+    pub headers: BTreeMap<String, String>,
+    /// 到达最终文档前经过的重定向
+    pub redirect_chain: Vec<RedirectHop>,
+    /// 最终文档的TLS证书是否有效，非HTTPS或Chrome无法判断时为 `None`
+    pub tls_valid: Option<bool>,
+}
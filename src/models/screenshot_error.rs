@@ -0,0 +1,242 @@
+use std::fmt;
+
+use crate::models::failure::FailureCategory;
+
+/// 截图流程中的结构化错误
+///
+/// 浏览器相关的错误根据 Chrome 返回的 `net::ERR_*` 错误码和 headless_chrome 的错误信息归类，
+/// 便于在日志中按类型筛选失败目标。
+#[derive(Debug)]
+pub enum ScreenshotError {
+    /// 域名解析失败
+    Dns(String),
+    /// 连接被拒绝
+    ConnectionRefused(String),
+    /// 连接被重置或中断
+    ConnectionReset(String),
+    /// TLS/证书错误
+    Tls(String),
+    /// 导航或页面事件等待超时
+    Timeout(String),
+    /// 截图整体超过时限，浏览器已被强制结束
+    DeadlineExceeded(u64),
+    /// 服务器返回错误状态码且页面无法渲染
+    HttpStatus(u16),
+    /// 浏览器启动失败
+    BrowserLaunch(String),
+    /// 浏览器运行中崩溃或连接断开
+    BrowserCrash(String),
+    /// 其他导航错误
+    Navigation(String),
+    /// 文件读写错误
+    Io(std::io::Error),
+    /// CSV 读写错误
+    Csv(csv::Error),
+    /// JSON 序列化错误
+    Json(serde_json::Error),
+    /// 结果数据库错误
+    Sqlite(rusqlite::Error),
+    /// 未归类的错误
+    Other(String),
+}
+
+/// 浏览器临时性的启动失败（端口占用等），可以重试
+const TRANSIENT_LAUNCH_PATTERNS: &[&str] = &[
+    "Chrome launched, but didn't give us a WebSocket URL",
+    "no available ports",
+    "debugging port is already in use",
+];
+
+/// 由错误信息构造对应类型的错误
+type ErrorBuilder = fn(String) -> ScreenshotError;
+
+/// 浏览器错误信息的特征文本和对应的错误类型，按顺序匹配，先匹配到的生效
+///
+/// 重试判断由错误类型决定（见 [`ScreenshotError::category`]），新增错误码只需改这一张表。
+const BROWSER_MESSAGE_PATTERNS: &[(&[&str], ErrorBuilder)] = &[
+    (
+        &[
+            "net::ERR_NAME_NOT_RESOLVED",
+            "net::ERR_NAME_RESOLUTION_FAILED",
+        ],
+        ScreenshotError::Dns,
+    ),
+    (
+        &[
+            "net::ERR_CONNECTION_REFUSED",
+            "net::ERR_ADDRESS_UNREACHABLE",
+        ],
+        ScreenshotError::ConnectionRefused,
+    ),
+    (
+        &[
+            "net::ERR_CONNECTION_RESET",
+            "net::ERR_CONNECTION_CLOSED",
+            "net::ERR_CONNECTION_ABORTED",
+            "net::ERR_EMPTY_RESPONSE",
+            "net::ERR_NETWORK_CHANGED",
+        ],
+        ScreenshotError::ConnectionReset,
+    ),
+    (
+        &["net::ERR_CERT_", "net::ERR_SSL_", "net::ERR_BAD_SSL_"],
+        ScreenshotError::Tls,
+    ),
+    (
+        &[
+            "net::ERR_TIMED_OUT",
+            "net::ERR_CONNECTION_TIMED_OUT",
+            "The event waited for never came",
+        ],
+        ScreenshotError::Timeout,
+    ),
+    (TRANSIENT_LAUNCH_PATTERNS, ScreenshotError::BrowserLaunch),
+    (
+        &[
+            "sandbox(false)",
+            "Could not auto detect a chrome executable",
+        ],
+        ScreenshotError::BrowserLaunch,
+    ),
+    (
+        &["underlying connection is closed", "Target closed"],
+        ScreenshotError::BrowserCrash,
+    ),
+    (
+        &["Navigate failed", "net::ERR_"],
+        ScreenshotError::Navigation,
+    ),
+];
+
+/// 错误信息是否包含任一特征文本
+fn contains_any(message: &str, patterns: &[&str]) -> bool {
+    patterns.iter().any(|pattern| message.contains(pattern))
+}
+
+impl ScreenshotError {
+    /// 根据浏览器返回的错误信息构造错误
+    pub fn from_browser_message(message: String) -> Self {
+        match BROWSER_MESSAGE_PATTERNS
+            .iter()
+            .find(|(patterns, _)| contains_any(&message, patterns))
+        {
+            Some((_, build)) => build(message),
+            None => ScreenshotError::Other(message),
+        }
+    }
+
+    /// 错误类型标识，写入日志的 `error_kind` 列
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScreenshotError::Dns(_) => "dns",
+            ScreenshotError::ConnectionRefused(_) => "connection_refused",
+            ScreenshotError::ConnectionReset(_) => "connection_reset",
+            ScreenshotError::Tls(_) => "tls",
+            ScreenshotError::Timeout(_) => "timeout",
+            ScreenshotError::DeadlineExceeded(_) => "deadline_exceeded",
+            ScreenshotError::HttpStatus(_) => "http_status",
+            ScreenshotError::BrowserLaunch(_) => "browser_launch",
+            ScreenshotError::BrowserCrash(_) => "browser_crash",
+            ScreenshotError::Navigation(_) => "navigation",
+            ScreenshotError::Io(_) => "io",
+            ScreenshotError::Csv(_) => "csv",
+            ScreenshotError::Json(_) => "json",
+            ScreenshotError::Sqlite(_) => "sqlite",
+            ScreenshotError::Other(_) => "other",
+        }
+    }
+
+    /// 用于重试判断的失败分类
+    pub fn category(&self) -> FailureCategory {
+        match self {
+            ScreenshotError::Timeout(_) => FailureCategory::NavigationTimeout,
+            ScreenshotError::ConnectionReset(_) => FailureCategory::ConnectionReset,
+            ScreenshotError::BrowserCrash(_) => FailureCategory::BrowserCrash,
+            // 整体超时的目标多为拖延连接的主机，重试只会再次占满时限，因此不重试
+            ScreenshotError::DeadlineExceeded(_) => FailureCategory::Other,
+            ScreenshotError::Dns(_) => FailureCategory::Dns,
+            // 启动失败只有临时性的情况（端口占用等）才可重试
+            ScreenshotError::BrowserLaunch(msg) if contains_any(msg, TRANSIENT_LAUNCH_PATTERNS) => {
+                FailureCategory::BrowserCrash
+            }
+            _ => FailureCategory::Other,
+        }
+    }
+
+    /// 该错误是否可以重试
+    pub fn is_retryable(&self) -> bool {
+        self.category().is_retryable()
+    }
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::Dns(msg) => write!(f, "域名解析失败: {}", msg),
+            ScreenshotError::ConnectionRefused(msg) => write!(f, "连接被拒绝: {}", msg),
+            ScreenshotError::ConnectionReset(msg) => write!(f, "连接被重置: {}", msg),
+            ScreenshotError::Tls(msg) => write!(f, "TLS错误: {}", msg),
+            ScreenshotError::Timeout(msg) => write!(f, "超时: {}", msg),
+            ScreenshotError::DeadlineExceeded(seconds) => {
+                write!(f, "截图超过 {} 秒未完成，已强制结束浏览器", seconds)
+            }
+            ScreenshotError::HttpStatus(status) => write!(f, "HTTP错误状态码: {}", status),
+            ScreenshotError::BrowserLaunch(msg) => write!(f, "浏览器启动失败: {}", msg),
+            ScreenshotError::BrowserCrash(msg) => write!(f, "浏览器崩溃: {}", msg),
+            ScreenshotError::Navigation(msg) => write!(f, "导航失败: {}", msg),
+            ScreenshotError::Io(e) => write!(f, "文件读写失败: {}", e),
+            ScreenshotError::Csv(e) => write!(f, "CSV读写失败: {}", e),
+            ScreenshotError::Json(e) => write!(f, "JSON序列化失败: {}", e),
+            ScreenshotError::Sqlite(e) => write!(f, "结果数据库操作失败: {}", e),
+            ScreenshotError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ScreenshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ScreenshotError::Io(e) => Some(e),
+            ScreenshotError::Csv(e) => Some(e),
+            ScreenshotError::Json(e) => Some(e),
+            ScreenshotError::Sqlite(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for ScreenshotError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::from_browser_message(format!("{:#}", error))
+    }
+}
+
+impl From<std::io::Error> for ScreenshotError {
+    fn from(error: std::io::Error) -> Self {
+        ScreenshotError::Io(error)
+    }
+}
+
+impl From<csv::Error> for ScreenshotError {
+    fn from(error: csv::Error) -> Self {
+        ScreenshotError::Csv(error)
+    }
+}
+
+impl From<serde_json::Error> for ScreenshotError {
+    fn from(error: serde_json::Error) -> Self {
+        ScreenshotError::Json(error)
+    }
+}
+
+impl From<rusqlite::Error> for ScreenshotError {
+    fn from(error: rusqlite::Error) -> Self {
+        ScreenshotError::Sqlite(error)
+    }
+}
+
+impl From<tokio::task::JoinError> for ScreenshotError {
+    fn from(error: tokio::task::JoinError) -> Self {
+        ScreenshotError::Other(format!("截图任务异常终止: {}", error))
+    }
+}
@@ -1,6 +1,13 @@
 use regex::Regex;
 use std::fmt;
 
+use crate::models::content_tag::ContentTag;
+use crate::models::failure::FailureCategory;
+use crate::models::page_class::PageClass;
+use crate::models::page_response::PageResponse;
+use crate::models::screenshot_error::ScreenshotError;
+use crate::models::technology::Technology;
+
 /// 目标类型枚举
 #[derive(Debug, Clone, PartialEq)]
 pub enum TargetType {
@@ -26,6 +33,8 @@ impl fmt::Display for TargetType {
 pub struct Target {
     pub url: String,
     pub original_text: String,
+    /// 在输入文件中的序号（从1开始），未从文件读取时为0
+    pub index: usize,
 }
 
 impl Target {
@@ -46,9 +55,16 @@ impl Target {
         Some(Target {
             url,
             original_text: trimmed,
+            index: 0,
         })
     }
 
+    /// 设置在输入文件中的序号
+    pub fn with_index(mut self, index: usize) -> Self {
+        self.index = index;
+        self
+    }
+
     /// 获取目标类型
     pub fn get_type(&self) -> TargetType {
         let url_regex = Regex::new(r"^https?://").unwrap();
@@ -68,16 +84,41 @@ impl Target {
         }
     }
 
+    /// 获取目标的主机名和端口（未指定端口时按协议取默认端口）
+    pub fn get_host_and_port(&self) -> (String, u16) {
+        let (scheme, rest) = self.url.split_once("://").unwrap_or(("http", &self.url));
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let authority = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host);
+        let default_port = if scheme.eq_ignore_ascii_case("https") {
+            443
+        } else {
+            80
+        };
+
+        // IPv6 地址形如 [::1]:8080
+        let (host, port) = if let Some(stripped) = authority.strip_prefix('[') {
+            match stripped.split_once(']') {
+                Some((host, rest)) => (host, rest.strip_prefix(':')),
+                None => (stripped, None),
+            }
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+
+        let port = port.and_then(|p| p.parse().ok()).unwrap_or(default_port);
+        (host.to_ascii_lowercase(), port)
+    }
+
     /// 获取清理后的文件名
     pub fn get_clean_filename(&self) -> String {
         self.original_text
             .replace("://", "_")
-            .replace('/', "_")
-            .replace(':', "_")
-            .replace('?', "_")
-            .replace('=', "_")
-            .replace('&', "_")
-            .replace('%', "_")
+            .replace(['/', ':', '?', '=', '&', '%'], "_")
     }
 }
 
@@ -88,6 +129,28 @@ pub struct ScreenshotResult {
     pub success: bool,
     pub file_path: Option<String>,
     pub error_message: Option<String>,
+    /// 实际尝试次数
+    pub attempts: u32,
+    /// 最终失败的分类
+    pub error_category: Option<FailureCategory>,
+    /// 最终失败的错误类型
+    pub error_kind: Option<&'static str>,
+    /// 主文档的HTTP状态码
+    pub status_code: Option<u16>,
+    /// 页面标题
+    pub title: Option<String>,
+    /// 主文档的响应信息
+    pub response: Option<PageResponse>,
+    /// 截图的感知哈希
+    pub perceptual_hash: Option<u64>,
+    /// 页面分类，仅成功的截图有值
+    pub page_class: Option<PageClass>,
+    /// 识别出的技术
+    pub technologies: Vec<Technology>,
+    /// 页面内容标签
+    pub tags: Vec<ContentTag>,
+    /// 缩略图路径
+    pub thumbnail_path: Option<String>,
 }
 
 impl ScreenshotResult {
@@ -97,15 +160,89 @@ impl ScreenshotResult {
             success: true,
             file_path: Some(file_path),
             error_message: None,
+            attempts: 1,
+            error_category: None,
+            error_kind: None,
+            status_code: None,
+            title: None,
+            response: None,
+            perceptual_hash: None,
+            page_class: None,
+            technologies: Vec::new(),
+            tags: Vec::new(),
+            thumbnail_path: None,
         }
     }
 
-    pub fn failure(target: Target, error_message: String) -> Self {
+    pub fn failure(target: Target, error: &ScreenshotError) -> Self {
         Self {
             target,
             success: false,
             file_path: None,
-            error_message: Some(error_message),
+            error_message: Some(error.to_string()),
+            attempts: 1,
+            error_category: Some(error.category()),
+            error_kind: Some(error.kind()),
+            status_code: match error {
+                ScreenshotError::HttpStatus(code) => Some(*code),
+                _ => None,
+            },
+            title: None,
+            response: None,
+            perceptual_hash: None,
+            page_class: None,
+            technologies: Vec::new(),
+            tags: Vec::new(),
+            thumbnail_path: None,
         }
     }
+
+    /// 设置实际尝试次数
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    /// 设置主文档的响应信息
+    pub fn with_response(mut self, response: PageResponse) -> Self {
+        self.status_code = response.status_code;
+        self.response = Some(response);
+        self
+    }
+
+    /// 设置截图的感知哈希
+    pub fn with_perceptual_hash(mut self, perceptual_hash: Option<u64>) -> Self {
+        self.perceptual_hash = perceptual_hash;
+        self
+    }
+
+    /// 设置页面分类
+    pub fn with_page_class(mut self, page_class: PageClass) -> Self {
+        self.page_class = Some(page_class);
+        self
+    }
+
+    /// 设置识别出的技术
+    pub fn with_technologies(mut self, technologies: Vec<Technology>) -> Self {
+        self.technologies = technologies;
+        self
+    }
+
+    /// 设置页面内容标签
+    pub fn with_tags(mut self, tags: Vec<ContentTag>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// 设置缩略图路径
+    pub fn with_thumbnail_path(mut self, thumbnail_path: Option<String>) -> Self {
+        self.thumbnail_path = thumbnail_path;
+        self
+    }
+
+    /// 设置页面标题
+    pub fn with_title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
 }
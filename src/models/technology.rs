@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 指纹识别出的技术
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Technology {
+    pub name: String,
+    /// 从响应头或页面中提取到的版本号
+    #[serde(default)]
+    pub version: Option<String>,
+    /// 分类，例如 "Web servers"、"CMS"
+    #[serde(default)]
+    pub categories: Vec<String>,
+}
+
+impl Technology {
+    /// 解析 `Display` 输出的 "名称 版本" 形式，CSV 日志中不保存分类
+    pub fn parse(label: &str) -> Option<Self> {
+        let label = label.trim();
+        if label.is_empty() {
+            return None;
+        }
+
+        let (name, version) = match label.rsplit_once(' ') {
+            Some((name, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => {
+                (name, Some(version.to_string()))
+            }
+            _ => (label, None),
+        };
+        Some(Self {
+            name: name.to_string(),
+            version,
+            categories: Vec::new(),
+        })
+    }
+}
+
+impl fmt::Display for Technology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {}", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt::Write;
+
+use crate::analysis::run_diff::RunDiff;
+use crate::file_io::file_operations::ScreenshotRecord;
+use crate::report::markdown_report::MarkdownReport;
+use crate::utils::path_utils::PathUtils;
+
+/// 报告文件名
+pub const DIFF_REPORT_FILENAME: &str = "diff_report.md";
+
+/// 两次运行的对比报告（Markdown）
+pub struct DiffReport;
+
+impl DiffReport {
+    /// 渲染报告，对比图链接相对于 `base_dir`
+    pub fn render(diff: &RunDiff, base_dir: &str) -> String {
+        let escape = MarkdownReport::escape;
+
+        let mut out = String::new();
+        let _ = writeln!(out, "# 截图对比报告\n");
+        let _ = writeln!(out, "| 新增 | 移除 | 变化 | 未变化 |");
+        let _ = writeln!(out, "| ---: | ---: | ---: | ---: |");
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len(),
+            diff.unchanged_count
+        );
+
+        let _ = writeln!(out, "\n## 变化的目标 ({})", diff.changed.len());
+        for changed in &diff.changed {
+            let _ = writeln!(out, "\n### {}\n", escape(&changed.target));
+            for reason in &changed.reasons {
+                let _ = writeln!(out, "- {}", escape(reason));
+            }
+            if let Some(path) = &changed.side_by_side_path {
+                let link = PathUtils::to_url_path(&PathUtils::relative_path(base_dir, path));
+                let _ = writeln!(out, "\n![{} 对比]({})", escape(&changed.target), link);
+            }
+            if let Some(path) = &changed.highlight_path {
+                let link = PathUtils::to_url_path(&PathUtils::relative_path(base_dir, path));
+                let _ = writeln!(out, "\n[差异高亮图]({})", link);
+            }
+        }
+
+        Self::render_list(&mut out, "新增的目标", &diff.added);
+        Self::render_list(&mut out, "移除的目标", &diff.removed);
+        out
+    }
+
+    fn render_list(out: &mut String, heading: &str, records: &[ScreenshotRecord]) {
+        let _ = writeln!(out, "\n## {} ({})\n", heading, records.len());
+        for record in records {
+            let status = match (record.status_code, record.success) {
+                (Some(code), _) => code.to_string(),
+                (None, true) => "成功".to_string(),
+                (None, false) => "失败".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "- {} ({}){}",
+                MarkdownReport::escape(&record.target),
+                status,
+                record
+                    .title
+                    .as_deref()
+                    .map(|title| format!(" - {}", MarkdownReport::escape(title)))
+                    .unwrap_or_default()
+            );
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use crate::file_io::file_operations::ScreenshotRecord;
+
+/// 状态和标题都相同的一组截图
+#[derive(Debug)]
+pub struct ReportGroup<'a> {
+    /// 状态描述，例如 "HTTP 200" 或 "错误: dns"
+    pub status: String,
+    /// 页面标题，没有标题时为 None
+    pub title: Option<&'a str>,
+    pub records: Vec<&'a ScreenshotRecord>,
+}
+
+impl<'a> ReportGroup<'a> {
+    /// 按状态和标题分组，数量多的组排在前面，数量相同时按状态和标题排序
+    pub fn group(records: &'a [ScreenshotRecord]) -> Vec<ReportGroup<'a>> {
+        let mut groups: Vec<ReportGroup<'a>> = Vec::new();
+        let mut index: HashMap<(String, Option<&'a str>), usize> = HashMap::new();
+
+        for record in records {
+            let key = (Self::status_of(record), record.title.as_deref());
+            match index.get(&key) {
+                Some(&position) => groups[position].records.push(record),
+                None => {
+                    index.insert(key.clone(), groups.len());
+                    groups.push(ReportGroup {
+                        status: key.0,
+                        title: key.1,
+                        records: vec![record],
+                    });
+                }
+            }
+        }
+
+        groups.sort_by(|a, b| {
+            b.records
+                .len()
+                .cmp(&a.records.len())
+                .then_with(|| a.status.cmp(&b.status))
+                .then_with(|| a.title.cmp(&b.title))
+        });
+        groups
+    }
+
+    /// 记录的状态描述，噪音页面附带页面分类
+    pub fn status_of(record: &ScreenshotRecord) -> String {
+        let status = match (record.status_code, record.success) {
+            (Some(code), _) => format!("HTTP {}", code),
+            (None, true) => "无状态码".to_string(),
+            (None, false) => format!("错误: {}", record.error_kind.as_deref().unwrap_or("other")),
+        };
+        match record.page_class.as_deref() {
+            Some(page_class) if record.is_noise() => format!("{} ({})", status, page_class),
+            _ => status,
+        }
+    }
+}
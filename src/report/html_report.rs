@@ -0,0 +1,115 @@
+use log::info;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::file_io::file_operations::{FileOperations, ScreenshotRecord};
+use crate::models::screenshot_error::ScreenshotError;
+use crate::utils::path_utils::PathUtils;
+
+/// 报告页面模板，数据以JSON形式嵌入，筛选、排序和分页均在浏览器中完成
+const GALLERY_TEMPLATE: &str = include_str!("templates/gallery.html");
+
+/// 报告文件名
+pub const HTML_REPORT_FILENAME: &str = "index.html";
+
+/// 嵌入报告页面的数据
+#[derive(Serialize)]
+struct ReportData<'a> {
+    generated_at: u64,
+    records: Vec<ReportEntry<'a>>,
+}
+
+/// 报告中的单个目标
+#[derive(Serialize)]
+struct ReportEntry<'a> {
+    timestamp: u64,
+    target: &'a str,
+    target_type: &'a str,
+    success: bool,
+    status_code: Option<u16>,
+    title: Option<&'a str>,
+    error_message: Option<&'a str>,
+    error_kind: Option<&'a str>,
+    page_class: Option<&'a str>,
+    cluster_id: Option<usize>,
+    cluster_size: Option<usize>,
+    /// 识别出的技术，例如 "nginx 1.18"
+    technologies: Vec<String>,
+    tags: &'a [String],
+    /// 相对于报告文件的截图路径
+    screenshot: Option<String>,
+    /// 相对于报告文件的缩略图路径，图库中优先显示缩略图
+    thumbnail: Option<String>,
+}
+
+/// HTML 图库报告
+pub struct HtmlReport;
+
+impl HtmlReport {
+    /// 在截图目录中生成 `index.html`，返回报告路径
+    ///
+    /// 同一目标有多条记录时只展示最后一条。
+    pub fn generate(
+        records: &[ScreenshotRecord],
+        screenshots_dir: &str,
+    ) -> Result<String, ScreenshotError> {
+        let records = FileOperations::latest_records(records);
+        let html = Self::render(&records, screenshots_dir)?;
+
+        let report_path = Path::new(screenshots_dir).join(HTML_REPORT_FILENAME);
+        fs::write(&report_path, html)?;
+
+        let report_path = report_path.to_string_lossy().to_string();
+        info!("生成HTML报告: {}", report_path);
+        Ok(report_path)
+    }
+
+    /// 渲染报告页面
+    pub fn render(
+        records: &[ScreenshotRecord],
+        screenshots_dir: &str,
+    ) -> Result<String, ScreenshotError> {
+        let data = ReportData {
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            records: records
+                .iter()
+                .map(|record| ReportEntry {
+                    timestamp: record.timestamp,
+                    target: &record.target,
+                    target_type: &record.target_type,
+                    success: record.success,
+                    status_code: record.status_code,
+                    title: record.title.as_deref(),
+                    error_message: record.error_message.as_deref(),
+                    error_kind: record.error_kind.as_deref(),
+                    page_class: record.page_class.as_deref(),
+                    cluster_id: record.cluster_id,
+                    cluster_size: record.cluster_size,
+                    technologies: record
+                        .technologies
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    tags: &record.tags,
+                    screenshot: record.screenshot_path.as_deref().map(|path| {
+                        PathUtils::to_url_path(&PathUtils::relative_path(screenshots_dir, path))
+                    }),
+                    thumbnail: record.thumbnail_path.as_deref().map(|path| {
+                        PathUtils::to_url_path(&PathUtils::relative_path(screenshots_dir, path))
+                    }),
+                })
+                .collect(),
+        };
+
+        let json = serde_json::to_string(&data)?;
+
+        // 转义 '<'，防止数据中的 "</script>" 等内容提前结束脚本块
+        let json = json.replace('<', "\\u003c");
+        Ok(GALLERY_TEMPLATE.replace("{{REPORT_DATA}}", &json))
+    }
+}
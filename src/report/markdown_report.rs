@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::file_io::file_operations::{FileOperations, ScreenshotRecord};
+use crate::report::grouping::ReportGroup;
+use crate::utils::path_utils::PathUtils;
+
+/// 报告文件名
+pub const MARKDOWN_REPORT_FILENAME: &str = "report.md";
+
+/// Markdown 报告，可直接粘贴到渗透测试报告中
+pub struct MarkdownReport;
+
+impl MarkdownReport {
+    /// 渲染报告，截图链接相对于 `base_dir`
+    ///
+    /// 同一目标有多条记录时只保留最后一条。每个相似截图组只嵌入第一张截图，
+    /// 其余成员注明与哪个目标相似。
+    pub fn render(records: &[ScreenshotRecord], base_dir: &str) -> String {
+        let records = FileOperations::latest_records(records);
+        let groups = ReportGroup::group(&records);
+        let success_count = records.iter().filter(|record| record.success).count();
+
+        let mut out = String::new();
+        let _ = writeln!(out, "# 截图报告\n");
+        let _ = writeln!(
+            out,
+            "目标总数: {}，成功: {}，失败: {}\n",
+            records.len(),
+            success_count,
+            records.len() - success_count
+        );
+
+        let _ = writeln!(out, "## 概要\n");
+        let _ = writeln!(out, "| 状态 | 标题 | 数量 |");
+        let _ = writeln!(out, "| --- | --- | ---: |");
+        for group in &groups {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                Self::escape(&group.status),
+                Self::escape(group.title.unwrap_or("-")),
+                group.records.len()
+            );
+        }
+
+        let interesting: Vec<&ScreenshotRecord> = records
+            .iter()
+            .filter(|record| record.is_interesting())
+            .collect();
+        if !interesting.is_empty() {
+            let _ = writeln!(out, "\n## 值得关注\n");
+            let _ = writeln!(out, "| 目标 | 标签 | 标题 |");
+            let _ = writeln!(out, "| --- | --- | --- |");
+            for record in interesting {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} |",
+                    Self::escape(&record.target),
+                    Self::escape(&record.tags.join(", ")),
+                    Self::escape(record.title.as_deref().unwrap_or("-"))
+                );
+            }
+        }
+
+        // 已嵌入截图的相似组及其代表目标
+        let mut representatives: HashMap<usize, &str> = HashMap::new();
+        for group in &groups {
+            let _ = writeln!(
+                out,
+                "\n## {} · {} ({})",
+                Self::escape(&group.status),
+                Self::escape(group.title.unwrap_or("无标题")),
+                group.records.len()
+            );
+
+            for record in &group.records {
+                let _ = writeln!(out, "\n### {}\n", Self::escape(&record.target));
+
+                let mut details = Vec::new();
+                if let Some(final_url) = record
+                    .final_url
+                    .as_deref()
+                    .filter(|url| *url != record.target)
+                {
+                    details.push(format!("- 最终URL: {}", Self::escape(final_url)));
+                }
+                if record.is_interesting() {
+                    details.push(format!("- 标签: {}", Self::escape(&record.tags.join(", "))));
+                }
+                if !record.technologies.is_empty() {
+                    details.push(format!(
+                        "- 技术栈: {}",
+                        Self::escape(&record.technologies_label())
+                    ));
+                }
+                if let Some(message) = &record.error_message {
+                    details.push(format!("- 错误: {}", Self::escape(message)));
+                }
+                let mut show_image = true;
+                if let (Some(cluster_id), Some(cluster_size)) =
+                    (record.cluster_id, record.cluster_size)
+                    && cluster_size > 1
+                {
+                    match representatives.get(&cluster_id) {
+                        Some(representative) => {
+                            details.push(format!(
+                                "- 与 {} 的截图相似（相似组 #{}，共 {} 张）",
+                                Self::escape(representative),
+                                cluster_id,
+                                cluster_size
+                            ));
+                            show_image = false;
+                        }
+                        None => {
+                            representatives.insert(cluster_id, &record.target);
+                            details.push(format!(
+                                "- 相似组 #{} 的代表截图，共 {} 张",
+                                cluster_id, cluster_size
+                            ));
+                        }
+                    }
+                }
+                for detail in &details {
+                    let _ = writeln!(out, "{}", detail);
+                }
+
+                if show_image && let Some(path) = &record.screenshot_path {
+                    if !details.is_empty() {
+                        out.push('\n');
+                    }
+                    let link = PathUtils::to_url_path(&PathUtils::relative_path(base_dir, path));
+                    // 有缩略图时显示缩略图，点击打开原图
+                    match &record.thumbnail_path {
+                        Some(thumbnail) => {
+                            let thumbnail = PathUtils::to_url_path(&PathUtils::relative_path(
+                                base_dir, thumbnail,
+                            ));
+                            let _ = writeln!(
+                                out,
+                                "[![{}]({})]({})",
+                                Self::escape(&record.target),
+                                thumbnail,
+                                link
+                            );
+                        }
+                        None => {
+                            let _ = writeln!(out, "![{}]({})", Self::escape(&record.target), link);
+                        }
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    /// 转义 Markdown 特殊字符，换行替换为空格，保证内容留在同一行/单元格中
+    pub(crate) fn escape(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#' | '!' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                '\r' | '\n' => escaped.push(' '),
+                _ => escaped.push(c),
+            }
+        }
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(value: serde_json::Value) -> ScreenshotRecord {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn tags_and_absolute_links_are_safe() {
+        let records = [record(serde_json::json!({
+            "timestamp": 1,
+            "target": "http://a.example/x_y",
+            "target_type": "url",
+            "success": true,
+            "attempts": 1,
+            "screenshot_path": "C:\\shots\\a b.png",
+            "tags": ["admin_panel", "a|b*"],
+        }))];
+
+        let report = MarkdownReport::render(&records, "out");
+        assert!(report.contains("| http://a.example/x\\_y | admin\\_panel, a\\|b\\* | - |"));
+        assert!(report.contains("- 标签: admin\\_panel, a\\|b\\*"));
+        assert!(report.contains("(file:///C:/shots/a%20b.png)"));
+    }
+}
@@ -0,0 +1,9 @@
+//! 报告生成模块
+//!
+//! 根据截图日志生成便于人工审阅的报告
+
+pub mod diff_report;
+pub mod grouping;
+pub mod html_report;
+pub mod markdown_report;
+pub mod text_report;
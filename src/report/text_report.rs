@@ -0,0 +1,76 @@
+use std::fmt::Write;
+
+use crate::file_io::file_operations::{FileOperations, ScreenshotRecord};
+use crate::report::grouping::ReportGroup;
+use crate::utils::path_utils::PathUtils;
+
+/// 报告文件名
+pub const TEXT_REPORT_FILENAME: &str = "report.txt";
+
+/// 纯文本报告，按状态和标题分组列出目标
+pub struct TextReport;
+
+impl TextReport {
+    /// 渲染报告，截图路径相对于 `base_dir`
+    ///
+    /// 同一目标有多条记录时只保留最后一条。
+    pub fn render(records: &[ScreenshotRecord], base_dir: &str) -> String {
+        let records = FileOperations::latest_records(records);
+        let groups = ReportGroup::group(&records);
+        let success_count = records.iter().filter(|record| record.success).count();
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "截图报告 - 目标总数: {}，成功: {}，失败: {}",
+            records.len(),
+            success_count,
+            records.len() - success_count
+        );
+
+        for group in &groups {
+            let _ = writeln!(
+                out,
+                "\n[{}] {} ({})",
+                group.status,
+                Self::single_line(group.title.unwrap_or("无标题")),
+                group.records.len()
+            );
+
+            for record in &group.records {
+                let detail = match (&record.screenshot_path, &record.error_message) {
+                    (Some(path), _) => PathUtils::relative_path(base_dir, path),
+                    (None, Some(message)) => Self::single_line(message),
+                    (None, None) => String::new(),
+                };
+                let cluster = match (record.cluster_id, record.cluster_size) {
+                    (Some(cluster_id), Some(cluster_size)) if cluster_size > 1 => {
+                        format!("  (相似组 #{} ×{})", cluster_id, cluster_size)
+                    }
+                    _ => String::new(),
+                };
+                let tags: String = record
+                    .tags
+                    .iter()
+                    .map(|tag| format!("  #{}", tag))
+                    .collect();
+                let technologies = if record.technologies.is_empty() {
+                    String::new()
+                } else {
+                    format!("  [{}]", record.technologies_label())
+                };
+                let _ = writeln!(
+                    out,
+                    "  {}  {}{}{}{}",
+                    record.target, detail, tags, technologies, cluster
+                );
+            }
+        }
+
+        out
+    }
+
+    fn single_line(text: &str) -> String {
+        text.replace(['\r', '\n'], " ")
+    }
+}
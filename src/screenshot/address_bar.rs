@@ -0,0 +1,358 @@
+use chrono::{DateTime, Utc};
+use headless_chrome::Tab;
+use headless_chrome::protocol::cdp::Runtime;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage, imageops};
+use std::fs;
+use std::io::Cursor;
+
+use crate::config::address_bar::FrameTheme;
+use crate::models::page_response::PageResponse;
+use crate::models::screenshot_error::ScreenshotError;
+use crate::screenshot::text_painter::TextPainter;
+
+/// 地址栏高度（像素），注入的覆盖层和截图后绘制的地址栏相同，自定义模板超出部分被裁掉
+pub const ADDRESS_BAR_HEIGHT: u32 = 44;
+
+/// 截图后绘制的地址栏左右留白
+const COMPOSITE_PADDING: i32 = 12;
+
+/// 在页面顶部插入地址栏的函数，`this` 为 `document.documentElement`
+///
+/// 模板来自内置主题或用户指定的文件，解析为 HTML 后放入 Shadow DOM，与页面样式互不影响。
+/// URL、标题等值作为调用参数传入，只替换文本节点和属性中的占位符，不会被当作 HTML 或脚本解析；
+/// `<style>`、`<script>` 的内容和 `on*` 事件属性中的占位符不做替换。
+const ADDRESS_BAR_FUNCTION: &str = r#"function (template, values) {
+    var fill = function (text) {
+        return text.replace(/\{\{\s*(\w+)\s*\}\}/g, function (placeholder, name) {
+            return Object.prototype.hasOwnProperty.call(values, name) ? values[name] : placeholder;
+        });
+    };
+
+    var frame = document.createElement("template");
+    frame.innerHTML = template;
+    var walker = document.createTreeWalker(frame.content, NodeFilter.SHOW_ELEMENT | NodeFilter.SHOW_TEXT);
+    while (walker.nextNode()) {
+        var node = walker.currentNode;
+        if (node.nodeType === Node.TEXT_NODE) {
+            var parent = node.parentNode && node.parentNode.nodeName;
+            if (parent !== "STYLE" && parent !== "SCRIPT") {
+                node.nodeValue = fill(node.nodeValue);
+            }
+            continue;
+        }
+        Array.prototype.forEach.call(node.attributes, function (attr) {
+            if (attr.name.toLowerCase().indexOf("on") !== 0) {
+                node.setAttribute(attr.name, fill(attr.value));
+            }
+        });
+    }
+
+    var bar = document.createElement("div");
+    bar.id = "custom-address-bar";
+    bar.style.cssText = "position: fixed; top: 0; left: 0; width: 100%; height: 44px;"
+        + " overflow: hidden; margin: 0; padding: 0; border: 0; z-index: 2147483647;";
+    bar.attachShadow({ mode: "open" }).appendChild(frame.content);
+    this.insertBefore(bar, this.firstChild);
+}"#;
+
+/// 内置的地址栏主题模板
+const SAFARI_FRAME: &str = include_str!("frames/safari.html");
+const CHROME_LIGHT_FRAME: &str = include_str!("frames/chrome_light.html");
+const CHROME_DARK_FRAME: &str = include_str!("frames/chrome_dark.html");
+const FIREFOX_FRAME: &str = include_str!("frames/firefox.html");
+const MINIMAL_FRAME: &str = include_str!("frames/minimal.html");
+
+/// 截图中模拟浏览器地址栏的覆盖层
+pub struct AddressBar;
+
+impl AddressBar {
+    /// 读取地址栏模板，指定了自定义模板文件时使用该文件，否则使用主题对应的内置模板
+    pub fn template(theme: FrameTheme, custom: Option<&str>) -> Result<String, ScreenshotError> {
+        let Some(path) = custom else {
+            return Ok(match theme {
+                FrameTheme::MobileSafari => SAFARI_FRAME,
+                FrameTheme::ChromeLight => CHROME_LIGHT_FRAME,
+                FrameTheme::ChromeDark => CHROME_DARK_FRAME,
+                FrameTheme::Firefox => FIREFOX_FRAME,
+                FrameTheme::Minimal => MINIMAL_FRAME,
+            }
+            .to_string());
+        };
+
+        let template = fs::read_to_string(path)
+            .map_err(|e| ScreenshotError::Other(format!("读取地址栏模板失败 {}: {}", path, e)))?;
+        if template.trim().is_empty() {
+            return Err(ScreenshotError::Other(format!("地址栏模板为空: {}", path)));
+        }
+        Ok(template)
+    }
+
+    /// 用 `template` 在页面顶部插入显示 `url` 的地址栏
+    ///
+    /// 锁形或警告图标依据最终地址的协议和TLS证书状态选择。
+    pub fn inject(
+        tab: &Tab,
+        template: &str,
+        url: &str,
+        title: Option<&str>,
+        response: &PageResponse,
+    ) -> Result<(), ScreenshotError> {
+        let root = tab.evaluate("document.documentElement", false)?;
+        let object_id = root
+            .object_id
+            .ok_or_else(|| ScreenshotError::Other("页面没有根元素，无法添加地址栏".to_string()))?;
+
+        let values = Self::values(url, title, response, Utc::now());
+        let result = tab.call_method(Self::call(object_id, template, values))?;
+        if let Some(exception) = result.exception_details {
+            return Err(ScreenshotError::Other(format!(
+                "添加地址栏失败: {}",
+                exception.text
+            )));
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        Ok(())
+    }
+
+    /// 在未改动的截图上方拼接地址栏，第一行为 URL，第二行为状态码和截图时间（UTC）
+    pub fn composite(
+        page: &DynamicImage,
+        url: &str,
+        status_code: Option<u16>,
+        captured_at: DateTime<Utc>,
+    ) -> RgbaImage {
+        let width = page.width();
+        let mut canvas = RgbaImage::new(width, page.height() + ADDRESS_BAR_HEIGHT);
+
+        // 与覆盖层相同的浅灰渐变背景和底部分隔线
+        for y in 0..ADDRESS_BAR_HEIGHT {
+            let shade = if y == ADDRESS_BAR_HEIGHT - 1 {
+                0xb2
+            } else {
+                (0xf8 - 0x10 * y / (ADDRESS_BAR_HEIGHT - 1)) as u8
+            };
+            for x in 0..width {
+                canvas.put_pixel(x, y, Rgba([shade, shade, shade, 255]));
+            }
+        }
+        imageops::replace(
+            &mut canvas,
+            &page.to_rgba8(),
+            0,
+            i64::from(ADDRESS_BAR_HEIGHT),
+        );
+
+        let painter = TextPainter::new();
+        let max_width = (width as i32 - COMPOSITE_PADDING * 2).max(0) as f32;
+        let status =
+            status_code.map_or_else(|| "HTTP -".to_string(), |code| format!("HTTP {}", code));
+        let details = format!(
+            "{}  ·  {}",
+            status,
+            captured_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        painter.draw(
+            &mut canvas,
+            &painter.fit(url, 14.0, max_width),
+            COMPOSITE_PADDING,
+            5,
+            14.0,
+            Rgba([0x33, 0x33, 0x33, 255]),
+        );
+        painter.draw(
+            &mut canvas,
+            &painter.fit(&details, 11.0, max_width),
+            COMPOSITE_PADDING,
+            25,
+            11.0,
+            Rgba([0x66, 0x66, 0x66, 255]),
+        );
+        canvas
+    }
+
+    /// 编码为PNG
+    pub fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, ScreenshotError> {
+        let mut data = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .map_err(|e| ScreenshotError::Other(format!("编码截图失败: {}", e)))?;
+        Ok(data)
+    }
+
+    /// 模板占位符的取值
+    fn values(
+        url: &str,
+        title: Option<&str>,
+        response: &PageResponse,
+        captured_at: DateTime<Utc>,
+    ) -> serde_json::Value {
+        let final_url = response.final_url.as_deref().unwrap_or(url);
+        let is_https = final_url
+            .get(..8)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"));
+        let security = match (is_https, response.tls_valid) {
+            (true, Some(true)) => "secure",
+            (true, _) => "warning",
+            (false, _) => "insecure",
+        };
+
+        serde_json::json!({
+            "url": url,
+            "title": title.unwrap_or_default(),
+            "time": captured_at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            "status": response.status_code.map_or_else(|| "-".to_string(), |code| code.to_string()),
+            "security": security,
+        })
+    }
+
+    /// 构造在根元素上调用地址栏函数的 CDP 命令，模板和取值只出现在参数中
+    fn call(
+        object_id: String,
+        template: &str,
+        values: serde_json::Value,
+    ) -> Runtime::CallFunctionOn {
+        Runtime::CallFunctionOn {
+            function_declaration: ADDRESS_BAR_FUNCTION.to_string(),
+            object_id: Some(object_id),
+            arguments: Some(vec![
+                Runtime::CallArgument {
+                    value: Some(serde_json::Value::String(template.to_string())),
+                    unserializable_value: None,
+                    object_id: None,
+                },
+                Runtime::CallArgument {
+                    value: Some(values),
+                    unserializable_value: None,
+                    object_id: None,
+                },
+            ]),
+            silent: Some(false),
+            return_by_value: Some(false),
+            generate_preview: None,
+            user_gesture: None,
+            await_promise: Some(false),
+            execution_context_id: None,
+            object_group: None,
+            throw_on_side_effect: None,
+            serialization_options: None,
+            unique_context_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTILE_URLS: &[&str] = &[
+        "http://example.com/<script>alert(1)</script>",
+        "http://example.com/${alert(document.domain)}",
+        "http://example.com/`);alert(1);(`",
+        "http://example.com/');alert(1);('",
+        "http://example.com/\");alert(1);(\"",
+        "http://example.com/</div><img src=x onerror=alert(1)>",
+        "http://example.com/\\`${1+1}\\`",
+        "http://example.com/\u{2028}\u{2029}\n\r",
+        "http://example.com/&lt;b&gt;",
+    ];
+
+    fn response(final_url: &str, tls_valid: Option<bool>) -> PageResponse {
+        PageResponse {
+            status_code: Some(200),
+            final_url: Some(final_url.to_string()),
+            tls_valid,
+            ..PageResponse::default()
+        }
+    }
+
+    fn hostile_call(url: &str) -> Runtime::CallFunctionOn {
+        let values = AddressBar::values(
+            url,
+            Some(url),
+            &response(url, None),
+            DateTime::<Utc>::UNIX_EPOCH,
+        );
+        AddressBar::call("root".to_string(), SAFARI_FRAME, values)
+    }
+
+    #[test]
+    fn url_is_passed_only_as_argument() {
+        for url in HOSTILE_URLS {
+            let call = hostile_call(url);
+
+            assert_eq!(call.function_declaration, ADDRESS_BAR_FUNCTION);
+            let arguments = call.arguments.expect("缺少调用参数");
+            assert_eq!(arguments.len(), 2);
+            assert_eq!(
+                arguments[0].value,
+                Some(serde_json::Value::String(SAFARI_FRAME.to_string()))
+            );
+            let values = arguments[1].value.as_ref().expect("缺少占位符取值");
+            assert_eq!(values["url"], *url);
+            assert_eq!(values["title"], *url);
+        }
+    }
+
+    #[test]
+    fn url_survives_protocol_serialization() {
+        for url in HOSTILE_URLS {
+            let json = serde_json::to_value(hostile_call(url)).unwrap();
+
+            assert_eq!(json["functionDeclaration"], ADDRESS_BAR_FUNCTION);
+            assert_eq!(json["arguments"][0]["value"], SAFARI_FRAME);
+            assert_eq!(json["arguments"][1]["value"]["url"], *url);
+            assert_eq!(json["objectId"], "root");
+        }
+    }
+
+    #[test]
+    fn hostile_url_stays_out_of_script_body() {
+        // 取值只出现在调用参数中，函数体与 URL 无关
+        let url = "http://example.com/</div><img src=x onerror=alert(1)>";
+        let call = hostile_call(url);
+
+        assert!(!call.function_declaration.contains("<img"));
+        assert!(!call.function_declaration.contains("onerror"));
+        let arguments = serde_json::to_string(&call.arguments).unwrap();
+        assert!(arguments.contains(url));
+        assert_eq!(
+            call.function_declaration,
+            hostile_call("http://example.com/").function_declaration
+        );
+    }
+
+    #[test]
+    fn security_follows_scheme_and_certificate() {
+        let security = |final_url: &str, tls_valid: Option<bool>| {
+            AddressBar::values(
+                "http://example.com",
+                None,
+                &response(final_url, tls_valid),
+                DateTime::<Utc>::UNIX_EPOCH,
+            )["security"]
+                .clone()
+        };
+
+        assert_eq!(security("https://example.com/", Some(true)), "secure");
+        assert_eq!(security("HTTPS://example.com/", Some(true)), "secure");
+        assert_eq!(security("https://example.com/", Some(false)), "warning");
+        assert_eq!(security("https://example.com/", None), "warning");
+        assert_eq!(security("http://example.com/", Some(true)), "insecure");
+    }
+
+    #[test]
+    fn bundled_themes_use_placeholders() {
+        for theme in FrameTheme::NAMES {
+            let theme = FrameTheme::parse(theme).unwrap();
+            let template = AddressBar::template(theme, None).unwrap();
+
+            assert!(template.contains("{{url}}"), "{:?} 缺少 URL", theme);
+            assert!(
+                template.contains("{{security}}"),
+                "{:?} 缺少安全状态",
+                theme
+            );
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use headless_chrome::Browser;
+use log::warn;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use crate::models::screenshot_error::ScreenshotError;
+
+/// 单次截图的终止句柄
+///
+/// 阻塞线程中的浏览器操作无法被 tokio 取消，执行器在截图超过时限后通过该句柄
+/// 直接结束浏览器进程，使阻塞中的调用因连接断开而尽快返回。
+#[derive(Clone, Default)]
+pub struct CaptureHandle {
+    state: Arc<Mutex<HandleState>>,
+}
+
+#[derive(Default)]
+struct HandleState {
+    process_id: Option<u32>,
+    killed: bool,
+}
+
+impl CaptureHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记截图所用的浏览器进程
+    ///
+    /// 句柄已被终止时返回错误，调用方应立即放弃本次截图。
+    pub fn attach(&self, browser: &Browser) -> Result<(), ScreenshotError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| ScreenshotError::Other("截图句柄状态异常".to_string()))?;
+
+        if state.killed {
+            return Err(ScreenshotError::Other("截图已被终止".to_string()));
+        }
+        state.process_id = browser.get_process_id();
+        Ok(())
+    }
+
+    /// 终止已登记的浏览器进程，之后登记的浏览器也会被拒绝
+    pub fn kill(&self) {
+        let process_id = match self.state.lock() {
+            Ok(mut state) => {
+                state.killed = true;
+                state.process_id.take()
+            }
+            Err(_) => None,
+        };
+
+        if let Some(pid) = process_id
+            && let Err(e) = Self::kill_process(pid)
+        {
+            warn!("结束浏览器进程 {} 失败: {}", pid, e);
+        }
+    }
+
+    /// 是否已被终止
+    pub fn is_killed(&self) -> bool {
+        self.state.lock().map(|state| state.killed).unwrap_or(true)
+    }
+
+    #[cfg(windows)]
+    fn kill_process(pid: u32) -> std::io::Result<()> {
+        Command::new("taskkill")
+            .args(["/F", "/T", "/PID", &pid.to_string()])
+            .output()
+            .map(|_| ())
+    }
+
+    /// 结束浏览器及其渲染、GPU 等子进程
+    ///
+    /// 逐层暂停进程后再列出其子进程，遍历期间不会派生出漏网的新进程；
+    /// 父进程先结束的话子进程会被 init 收养，无法再按父子关系找到。
+    #[cfg(not(windows))]
+    fn kill_process(pid: u32) -> std::io::Result<()> {
+        let mut tree = vec![pid];
+        let mut index = 0;
+        while index < tree.len() {
+            let parent = tree[index].to_string();
+            Command::new("kill").args(["-STOP", &parent]).output()?;
+            tree.extend(Self::child_processes(&parent)?);
+            index += 1;
+        }
+
+        Command::new("kill")
+            .arg("-9")
+            .args(tree.iter().map(u32::to_string))
+            .output()
+            .map(|_| ())
+    }
+
+    #[cfg(not(windows))]
+    fn child_processes(pid: &str) -> std::io::Result<Vec<u32>> {
+        let output = Command::new("pgrep").args(["-P", pid]).output()?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn is_alive(pid: u32) -> bool {
+        // 已结束但未回收的进程状态为 Z
+        std::fs::read_to_string(format!("/proc/{}/stat", pid))
+            .map(|stat| !stat.contains(") Z"))
+            .unwrap_or(false)
+    }
+
+    #[test]
+    fn kills_the_whole_process_tree() {
+        let mut parent = Command::new("sh")
+            .args(["-c", "sleep 60 & sleep 60 & wait"])
+            .spawn()
+            .unwrap();
+        let pid = parent.id();
+
+        let started = Instant::now();
+        let mut children = Vec::new();
+        while children.len() < 2 && started.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+            children = CaptureHandle::child_processes(&pid.to_string()).unwrap();
+        }
+        assert_eq!(children.len(), 2);
+
+        CaptureHandle::kill_process(pid).unwrap();
+        parent.wait().unwrap();
+
+        let started = Instant::now();
+        while children.iter().any(|child| is_alive(*child)) {
+            assert!(started.elapsed() < Duration::from_secs(5), "子进程未被结束");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
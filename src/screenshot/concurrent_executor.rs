@@ -1,51 +1,141 @@
 use futures::stream::{self, StreamExt};
-use indicatif::{ProgressBar, ProgressStyle};
-use std::sync::Arc;
-use tokio::sync::Semaphore;
+use indicatif::{HumanDuration, ProgressBar, ProgressState, ProgressStyle};
+use log::{error, info, warn};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
 
 use crate::config::app_config::AppConfig;
-use crate::models::target::Target;
+use crate::config::retry_policy::RetryPolicy;
+use crate::file_io::file_operations::ScreenshotRecord;
+use crate::file_io::result_sink::{ResultSink, RunSummary};
+use crate::models::screenshot_error::ScreenshotError;
+use crate::models::target::{ScreenshotResult, Target};
 use crate::screenshot::async_screenshot_service::AsyncScreenshotService;
+use crate::screenshot::capture_handle::CaptureHandle;
+use crate::screenshot::rate_limiter::{HostPermit, RateLimiter};
+use crate::screenshot::shutdown_signal::ShutdownSignal;
+use crate::screenshot::thumbnailer::Thumbnailer;
+
+/// 运行开始前并发解析目标主机的数量
+const HOST_RESOLVE_CONCURRENCY: usize = 32;
 
 /// 并发执行器
 pub struct ConcurrentExecutor {
     semaphore: Arc<Semaphore>,
-    screenshot_service: Arc<AsyncScreenshotService>,
+    context: Arc<CaptureContext>,
+}
+
+/// 单个截图任务执行时共享的上下文
+struct CaptureContext {
+    screenshot_service: AsyncScreenshotService,
+    retry_policy: RetryPolicy,
+    capture_deadline: Duration,
+    shutdown: ShutdownSignal,
+    shutdown_grace: Duration,
+    rate_limiter: RateLimiter,
+    thumbnailer: Option<Thumbnailer>,
 }
 
 impl ConcurrentExecutor {
-    pub fn new(config: AppConfig) -> Self {
+    /// 创建执行器，指纹规则、地址栏模板或命名模板有误时返回错误，此时尚未启动任何浏览器
+    pub fn new(config: AppConfig) -> Result<Self, ScreenshotError> {
         let concurrency = config.concurrency;
-        Self {
+        let context = CaptureContext {
+            retry_policy: config.retry.clone(),
+            capture_deadline: config.get_capture_deadline(),
+            shutdown: ShutdownSignal::new(),
+            shutdown_grace: config.get_shutdown_grace(),
+            rate_limiter: RateLimiter::new(config.rate_limit.clone()),
+            thumbnailer: config
+                .thumbnail
+                .clone()
+                .map(|thumbnail| Thumbnailer::new(thumbnail, &config.screenshots_dir))
+                .transpose()?,
+            screenshot_service: AsyncScreenshotService::new(config)?,
+        };
+        Ok(Self {
             semaphore: Arc::new(Semaphore::new(concurrency)),
-            screenshot_service: Arc::new(AsyncScreenshotService::new(config)),
+            context: Arc::new(context),
+        })
+    }
+
+    /// 使用外部的停止信号
+    ///
+    /// 信号触发后不再调度新的目标，进行中的截图最多再等待宽限期，之后强制结束浏览器。
+    /// 未完成的目标不会传给结果回调。
+    pub fn with_shutdown_signal(mut self, shutdown: ShutdownSignal) -> Self {
+        if let Some(context) = Arc::get_mut(&mut self.context) {
+            context.shutdown = shutdown;
         }
+        self
     }
 
     /// 并发执行截图任务，实时返回每个任务的结果
+    ///
+    /// 启用限速时，开始截图前会先解析所有目标的主机名（最多 [`HOST_RESOLVE_CONCURRENCY`] 个同时解析），
+    /// 以便按IP交错排列目标和估算ETA；目标很多或DNS很慢时，第一次截图前会有一段等待。
+    ///
+    /// 返回已完成目标中的成功数和失败数
     pub async fn execute_concurrent_screenshots<F>(
         &self,
         targets: Vec<Target>,
         mut on_result: F,
     ) -> (usize, usize)
     where
-        F: FnMut(
-                &Target,
-                Result<
-                    crate::models::target::ScreenshotResult,
-                    Box<dyn std::error::Error + Send + Sync>,
-                >,
-            ) + Send
-            + Sync
-            + 'static,
+        F: FnMut(&Target, Result<ScreenshotResult, ScreenshotError>) + Send + Sync + 'static,
     {
         let total_tasks = targets.len();
 
+        // 按限速器的分组键（解析出的IP）归并目标，同一IP上的虚拟主机视为同一主机
+        let rate_limiter = &self.context.rate_limiter;
+        if rate_limiter.is_enabled() {
+            info!("已启用限速，正在解析 {} 个目标的主机地址...", total_tasks);
+        }
+        let targets: Vec<(String, Target)> = stream::iter(targets)
+            .map(|target| async move { (rate_limiter.host_key(&target).await, target) })
+            .buffered(HOST_RESOLVE_CONCURRENCY)
+            .collect()
+            .await;
+
+        // 启用限速时按主机交错排列，避免同一主机的目标扎堆占满任务缓冲
+        let targets = if rate_limiter.is_enabled() {
+            Self::interleave_by_host(targets)
+        } else {
+            targets
+        };
+
+        // 限速条件下剩余目标所需的最短时间，作为ETA的下限
+        let mut remaining_per_host: HashMap<String, usize> = HashMap::new();
+        for (host, _) in &targets {
+            *remaining_per_host.entry(host.clone()).or_default() += 1;
+        }
+        let eta_floor_secs = Arc::new(AtomicU64::new(0));
+        let update_eta_floor = |remaining_per_host: &HashMap<String, usize>| {
+            let remaining_total = remaining_per_host.values().sum();
+            let max_per_host = remaining_per_host.values().copied().max().unwrap_or(0);
+            let floor = rate_limiter.minimum_remaining_time(remaining_total, max_per_host);
+            eta_floor_secs.store(floor.as_secs(), Ordering::Relaxed);
+        };
+        update_eta_floor(&remaining_per_host);
+
         // 创建单个进度条
         let progress_bar = ProgressBar::new(total_tasks as u64);
+        let eta_floor_secs_clone = Arc::clone(&eta_floor_secs);
         let style = ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta}) 成功: {msg}")
             .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .with_key(
+                "eta",
+                move |state: &ProgressState, w: &mut dyn fmt::Write| {
+                    let floor =
+                        Duration::from_secs(eta_floor_secs_clone.load(Ordering::Relaxed));
+                    let _ = write!(w, "{:#}", HumanDuration(state.eta().max(floor)));
+                },
+            )
             .progress_chars("█▓▒░");
         progress_bar.set_style(style);
         progress_bar.set_message("0");
@@ -54,46 +144,35 @@ impl ConcurrentExecutor {
         let fail_count = Arc::new(std::sync::atomic::AtomicUsize::new(0));
 
         // 保存原始目标的引用，避免移动后无法访问
-        let targets_arc: Vec<Arc<Target>> = targets
-            .iter()
-            .map(|target| Arc::new(target.clone()))
+        let targets_arc: Vec<(String, Arc<Target>)> = targets
+            .into_iter()
+            .map(|(host, target)| (host, Arc::new(target)))
             .collect();
 
         // 使用流处理任务，实现实时更新
-        let tasks = stream::iter(targets_arc.clone().into_iter().enumerate())
-            .map(|(index, target_arc)| {
+        let tasks = stream::iter(targets_arc.into_iter().enumerate())
+            .map(|(index, (host, target_arc))| {
                 let target_arc_clone = Arc::clone(&target_arc);
                 let semaphore = Arc::clone(&self.semaphore);
-                let screenshot_service = Arc::clone(&self.screenshot_service);
+                let context = Arc::clone(&self.context);
                 let success_count_clone = Arc::clone(&success_count);
                 let fail_count_clone = Arc::clone(&fail_count);
 
                 async move {
-                    // 获取信号量许可
-                    let permit_result = semaphore.acquire().await;
-                    let _permit = match permit_result {
-                        Ok(permit) => permit,
-                        Err(e) => {
-                            eprintln!("获取信号量许可失败: {}", e);
-                            return (
-                                index,
-                                target_arc,
-                                Err(Box::new(std::io::Error::new(
-                                    std::io::ErrorKind::Other,
-                                    "获取并发许可失败",
-                                ))
-                                    as Box<dyn std::error::Error + Send + Sync>),
-                            );
+                    let result = match context
+                        .capture_with_retry(&semaphore, target_arc_clone)
+                        .await
+                    {
+                        Some(Ok(screenshot_result)) => {
+                            Some(Ok(context.attach_thumbnail(screenshot_result).await))
                         }
+                        result => result,
                     };
 
-                    let result = screenshot_service
-                        .take_screenshot_async(target_arc_clone)
-                        .await;
-
                     // 更新计数器
                     match &result {
-                        Ok(screenshot_result) => {
+                        None => {}
+                        Some(Ok(screenshot_result)) => {
                             if screenshot_result.success {
                                 success_count_clone
                                     .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
@@ -101,25 +180,32 @@ impl ConcurrentExecutor {
                                 fail_count_clone.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
                             }
                         }
-                        Err(_) => {
+                        Some(Err(_)) => {
                             fail_count_clone.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
                         }
                     }
 
-                    (index, target_arc, result)
+                    (index, host, target_arc, result)
                 }
             })
-            .buffer_unordered(self.semaphore.available_permits());
+            // 处于退避等待的任务不占用许可，多预留一倍的缓冲让其他目标补位
+            .buffer_unordered(self.semaphore.available_permits() * 2);
 
         // 使用for_each处理任务流，实现实时更新
         use futures::pin_mut;
         pin_mut!(tasks);
 
-        let mut completed_count = 0;
-        while let Some((_index, target, result)) = tasks.next().await {
-            completed_count += 1;
+        while let Some((_index, host, target, result)) = tasks.next().await {
+            // 停止后未完成的目标不计入结果
+            let Some(result) = result else {
+                continue;
+            };
 
             // 更新进度条
+            if let Some(count) = remaining_per_host.get_mut(&host) {
+                *count = count.saturating_sub(1);
+            }
+            update_eta_floor(&remaining_per_host);
             progress_bar.inc(1);
             let current_success = success_count.load(std::sync::atomic::Ordering::SeqCst);
             progress_bar.set_message(current_success.to_string());
@@ -134,4 +220,271 @@ impl ConcurrentExecutor {
 
         (final_success, final_fail)
     }
+
+    /// 并发执行截图任务，把每个结果记录到输出目标并打印成功/失败日志
+    ///
+    /// 输出目标在开始前打开，打开失败时不执行截图；在所有已完成的结果写入后关闭，
+    /// 关闭失败只记录日志，仍返回运行统计，便于调用方继续生成报告等后续输出。
+    pub async fn execute_with_sink<S>(
+        &self,
+        targets: Vec<Target>,
+        sink: S,
+    ) -> Result<RunSummary, ScreenshotError>
+    where
+        S: ResultSink + 'static,
+    {
+        let sink = Arc::new(Mutex::new(sink));
+        sink.lock()
+            .map_err(|_| ScreenshotError::Other("结果输出锁已损坏".to_string()))?
+            .open()?;
+
+        let sink_clone = Arc::clone(&sink);
+        let (success_count, fail_count) = self
+            .execute_concurrent_screenshots(targets, move |target, result| {
+                let record = ScreenshotRecord::from_result(target, &result);
+                Self::log_record(&record);
+
+                if let Ok(mut sink) = sink_clone.lock()
+                    && let Err(e) = sink.record(&record)
+                {
+                    error!("写入结果失败 {}: {}", record.target, e);
+                }
+            })
+            .await;
+
+        let summary = RunSummary {
+            success_count,
+            fail_count,
+            interrupted: self.context.shutdown.is_triggered(),
+        };
+        match sink.lock() {
+            Ok(mut sink) => {
+                if let Err(e) = sink.finish(&summary) {
+                    error!("关闭结果输出失败: {}", e);
+                }
+            }
+            Err(_) => error!("结果输出锁已损坏，无法关闭结果输出"),
+        }
+        Ok(summary)
+    }
+
+    /// 打印单个目标的最终结果
+    fn log_record(record: &ScreenshotRecord) {
+        if record.success {
+            info!("✓ 成功截图: {}", record.target);
+        } else {
+            error!(
+                "✗ 截图失败 {} (尝试{}次): {}",
+                record.target,
+                record.attempts,
+                record.error_message.as_deref().unwrap_or("未知错误")
+            );
+        }
+    }
+
+    /// 按主机分组键轮流取出目标，使同一主机的目标在执行顺序中尽量分散
+    fn interleave_by_host(targets: Vec<(String, Target)>) -> Vec<(String, Target)> {
+        let mut hosts: Vec<String> = Vec::new();
+        let mut groups: HashMap<String, VecDeque<(String, Target)>> = HashMap::new();
+        for (host, target) in targets {
+            if !groups.contains_key(&host) {
+                hosts.push(host.clone());
+            }
+            groups
+                .entry(host.clone())
+                .or_default()
+                .push_back((host, target));
+        }
+
+        let mut interleaved = Vec::new();
+        while !groups.is_empty() {
+            for host in &hosts {
+                if let Some(group) = groups.get_mut(host) {
+                    if let Some(target) = group.pop_front() {
+                        interleaved.push(target);
+                    }
+                    if group.is_empty() {
+                        groups.remove(host);
+                    }
+                }
+            }
+        }
+        interleaved
+    }
+}
+
+impl CaptureContext {
+    /// 按重试策略执行单个目标的截图
+    ///
+    /// 每次尝试都单独获取并发许可，退避等待期间释放许可，不占用其他目标的并发名额。
+    /// 所有尝试都失败时返回带有尝试次数和失败分类的失败结果；
+    /// 收到停止信号而未完成时返回 `None`。
+    async fn capture_with_retry(
+        &self,
+        semaphore: &Semaphore,
+        target: Arc<Target>,
+    ) -> Option<Result<ScreenshotResult, ScreenshotError>> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+
+            // 依次获取主机许可、并发许可并等待限速时机，停止后不再开始新的截图
+            let (host_permit, permit) = tokio::select! {
+                biased;
+                _ = self.shutdown.wait() => return None,
+                permits = self.acquire_permits(semaphore, &target) => permits,
+            };
+            let permit = match permit {
+                Ok(permit) => permit,
+                Err(e) => {
+                    eprintln!("获取信号量许可失败: {}", e);
+                    return Some(Err(ScreenshotError::Other("获取并发许可失败".to_string())));
+                }
+            };
+
+            let error = match self.capture_with_deadline(Arc::clone(&target)).await? {
+                Ok(result) => return Some(Ok(result.with_attempts(attempt))),
+                Err(e) => e,
+            };
+            drop(permit);
+            drop(host_permit);
+
+            // 终端中的 Ctrl-C 会同时发给同一进程组的浏览器，停止后出现的失败多为此导致，
+            // 视为未完成，以便下次续跑时重新截图
+            if self.shutdown.is_triggered() {
+                return None;
+            }
+
+            let failure =
+                || ScreenshotResult::failure((*target).clone(), &error).with_attempts(attempt);
+            if !error.is_retryable() || !self.retry_policy.should_retry(attempt) {
+                return Some(Ok(failure()));
+            }
+
+            let delay = self.retry_policy.backoff_delay(attempt);
+            warn!(
+                "截图失败 {} (第{}次, {}): {}，{}ms 后重试",
+                target.original_text,
+                attempt,
+                error.category(),
+                error,
+                delay.as_millis()
+            );
+
+            // 停止时放弃剩余重试，记录最近一次的失败
+            tokio::select! {
+                _ = self.shutdown.wait() => return Some(Ok(failure())),
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+    }
+
+    /// 为成功的截图生成缩略图，此时已释放并发许可，失败时只记录警告
+    async fn attach_thumbnail(&self, result: ScreenshotResult) -> ScreenshotResult {
+        let (Some(thumbnailer), Some(path), true) = (
+            &self.thumbnailer,
+            result.file_path.as_deref(),
+            result.success,
+        ) else {
+            return result;
+        };
+
+        match thumbnailer.generate(path).await {
+            Ok(thumbnail_path) => result.with_thumbnail_path(Some(thumbnail_path)),
+            Err(e) => {
+                warn!("生成缩略图失败 {}: {}", path, e);
+                result
+            }
+        }
+    }
+
+    /// 获取目标所在主机的许可和全局并发许可，并等待到限速允许的时刻
+    ///
+    /// 先获取主机许可再获取并发许可，避免同一主机排队的目标占用全局并发名额。
+    async fn acquire_permits<'a>(
+        &self,
+        semaphore: &'a Semaphore,
+        target: &Target,
+    ) -> (
+        HostPermit,
+        Result<SemaphorePermit<'a>, tokio::sync::AcquireError>,
+    ) {
+        let host_permit = self.rate_limiter.acquire_host(target).await;
+        let permit = semaphore.acquire().await;
+        if permit.is_ok() {
+            self.rate_limiter.wait_turn(&host_permit).await;
+        }
+        (host_permit, permit)
+    }
+
+    /// 在总时限内执行一次截图
+    ///
+    /// 超时后强制结束浏览器进程并立即返回，阻塞线程会因连接断开自行退出，
+    /// 调用方随即释放并发许可。收到停止信号后宽限期内仍未完成的截图同样被结束，
+    /// 此时返回 `None`。
+    async fn capture_with_deadline(
+        &self,
+        target: Arc<Target>,
+    ) -> Option<Result<ScreenshotResult, ScreenshotError>> {
+        let handle = CaptureHandle::new();
+        let capture = self
+            .screenshot_service
+            .take_screenshot_async(target, handle.clone());
+        let grace_expired = async {
+            self.shutdown.wait().await;
+            tokio::time::sleep(self.shutdown_grace).await;
+        };
+
+        tokio::select! {
+            result = tokio::time::timeout(self.capture_deadline, capture) => match result {
+                Ok(result) => Some(result),
+                Err(_) => {
+                    handle.kill();
+                    Some(Err(ScreenshotError::DeadlineExceeded(
+                        self.capture_deadline.as_secs(),
+                    )))
+                }
+            },
+            _ = grace_expired => {
+                handle.kill();
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyed(host: &str, url: &str) -> (String, Target) {
+        (host.to_string(), Target::new(url.to_string()).unwrap())
+    }
+
+    #[test]
+    fn interleaves_by_limiter_key() {
+        // a 和 b 是同一IP上的虚拟主机，应与 c 轮流而不是各占一组
+        let targets = vec![
+            keyed("192.0.2.1", "http://a.example/1"),
+            keyed("192.0.2.1", "http://b.example/1"),
+            keyed("192.0.2.1", "http://a.example/2"),
+            keyed("192.0.2.2", "http://c.example/1"),
+            keyed("192.0.2.2", "http://c.example/2"),
+        ];
+
+        let order: Vec<String> = ConcurrentExecutor::interleave_by_host(targets)
+            .into_iter()
+            .map(|(_, target)| target.url)
+            .collect();
+        assert_eq!(
+            order,
+            [
+                "http://a.example/1",
+                "http://c.example/1",
+                "http://b.example/1",
+                "http://c.example/2",
+                "http://a.example/2",
+            ]
+        );
+    }
 }
@@ -0,0 +1,215 @@
+use chrono::{DateTime, Local, Utc};
+use image::{DynamicImage, Rgba, RgbaImage, imageops};
+use std::fs;
+
+use crate::models::screenshot_error::ScreenshotError;
+use crate::screenshot::text_painter::TextPainter;
+
+/// 水印文字大小
+const FOOTER_TEXT_SIZE: f32 = 11.0;
+
+/// 水印行高
+const FOOTER_LINE_HEIGHT: u32 = 15;
+
+/// 水印上下留白
+const FOOTER_PADDING_Y: u32 = 6;
+
+/// 水印左右留白
+const FOOTER_PADDING_X: i32 = 12;
+
+/// 同一行内各项之间的分隔符
+const FOOTER_SEPARATOR: &str = "  ·  ";
+
+/// 证明截图拍摄时间和来源的证据水印
+///
+/// 截图下方拼接一条页脚，写明截图时间（UTC和本地时间）、截图主机、运行ID和目标，
+/// 同样的信息写入PNG的文本块，裁掉可见的页脚后仍可读取。
+pub struct EvidenceStamp {
+    captured_at: DateTime<Utc>,
+    source_host: String,
+    run_id: String,
+    target: String,
+}
+
+impl EvidenceStamp {
+    pub fn new(run_id: &str, target: &str, captured_at: DateTime<Utc>) -> Self {
+        Self {
+            captured_at,
+            source_host: Self::source_host(),
+            run_id: run_id.to_string(),
+            target: target.to_string(),
+        }
+    }
+
+    /// 本机主机名，无法获取时为 `unknown`
+    pub fn source_host() -> String {
+        ["COMPUTERNAME", "HOSTNAME"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .chain(fs::read_to_string("/etc/hostname").ok())
+            .map(|host| host.trim().to_string())
+            .find(|host| !host.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    /// 写入PNG文本块的键值，按页脚中的顺序排列
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "Creation Time",
+                self.captured_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            ),
+            (
+                "Capture Time Local",
+                self.captured_at
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%dT%H:%M:%S%:z")
+                    .to_string(),
+            ),
+            ("Source Host", self.source_host.clone()),
+            ("Scan Run ID", self.run_id.clone()),
+            ("Target", self.target.clone()),
+            (
+                "Software",
+                format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            ),
+        ]
+    }
+
+    /// 在截图下方拼接水印页脚，宽度不够时自动换行
+    pub fn footer(&self, page: &DynamicImage) -> RgbaImage {
+        let painter = TextPainter::new();
+        let max_width = (page.width() as i32 - FOOTER_PADDING_X * 2).max(0) as f32;
+        let lines = Self::wrap(&painter, &self.footer_items(), max_width);
+
+        let height = FOOTER_PADDING_Y * 2 + FOOTER_LINE_HEIGHT * lines.len() as u32;
+        let mut canvas = RgbaImage::from_pixel(
+            page.width(),
+            page.height() + height,
+            Rgba([0x20, 0x21, 0x24, 255]),
+        );
+        imageops::replace(&mut canvas, &page.to_rgba8(), 0, 0);
+
+        for (index, line) in lines.iter().enumerate() {
+            let y = page.height() + FOOTER_PADDING_Y + FOOTER_LINE_HEIGHT * index as u32;
+            painter.draw(
+                &mut canvas,
+                line,
+                FOOTER_PADDING_X,
+                y as i32,
+                FOOTER_TEXT_SIZE,
+                Rgba([0xe8, 0xea, 0xed, 255]),
+            );
+        }
+        canvas
+    }
+
+    /// 编码为PNG并写入文本块，Latin-1 以外的内容使用 iTXt
+    pub fn encode_png(&self, image: &RgbaImage) -> Result<Vec<u8>, ScreenshotError> {
+        let to_error =
+            |e: png::EncodingError| ScreenshotError::Other(format!("编码截图失败: {}", e));
+
+        let mut data = Vec::new();
+        let mut encoder = png::Encoder::new(&mut data, image.width(), image.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (keyword, text) in self.fields() {
+            if text.chars().all(|c| u32::from(c) <= 0xff) {
+                encoder.add_text_chunk(keyword.to_string(), text)
+            } else {
+                encoder.add_itxt_chunk(keyword.to_string(), text)
+            }
+            .map_err(to_error)?;
+        }
+
+        let mut writer = encoder.write_header().map_err(to_error)?;
+        writer.write_image_data(image.as_raw()).map_err(to_error)?;
+        writer.finish().map_err(to_error)?;
+        Ok(data)
+    }
+
+    /// 页脚中显示的各项，内置字体不含中文，统一使用英文标签
+    fn footer_items(&self) -> Vec<String> {
+        let local = self.captured_at.with_timezone(&Local);
+        vec![
+            format!("UTC {}", self.captured_at.format("%Y-%m-%d %H:%M:%S")),
+            format!("Local {}", local.format("%Y-%m-%d %H:%M:%S %:z")),
+            format!("Host {}", self.source_host),
+            format!("Run {}", self.run_id),
+            format!("Target {}", self.target),
+        ]
+    }
+
+    /// 尽量把多项放在同一行，单项过长时截断
+    fn wrap(painter: &TextPainter, items: &[String], max_width: f32) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for item in items {
+            let item = painter.fit(item, FOOTER_TEXT_SIZE, max_width);
+            match lines.last_mut() {
+                Some(line)
+                    if painter.width(
+                        &format!("{}{}{}", line, FOOTER_SEPARATOR, item),
+                        FOOTER_TEXT_SIZE,
+                    ) <= max_width =>
+                {
+                    line.push_str(FOOTER_SEPARATOR);
+                    line.push_str(&item);
+                }
+                _ => lines.push(item),
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+
+    /// 读取PNG中的 tEXt 和 iTXt 文本块
+    fn read_chunks(data: &[u8]) -> (BTreeMap<String, String>, BTreeMap<String, String>) {
+        let reader = png::Decoder::new(Cursor::new(data)).read_info().unwrap();
+        let info = reader.info();
+        let latin1 = info
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+            .collect();
+        let utf8 = info
+            .utf8_text
+            .iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.get_text().unwrap()))
+            .collect();
+        (latin1, utf8)
+    }
+
+    #[test]
+    fn metadata_is_embedded_as_png_text_chunks() {
+        let captured_at = Utc.with_ymd_and_hms(2024, 5, 6, 7, 8, 9).unwrap();
+        let stamp = EvidenceStamp::new("run-1", "http://例子.测试/登录", captured_at);
+        let page = DynamicImage::new_rgba8(320, 200);
+
+        let footer = stamp.footer(&page);
+        assert_eq!(footer.width(), 320);
+        assert!(footer.height() > 200);
+
+        let data = stamp.encode_png(&footer).unwrap();
+        let (latin1, utf8) = read_chunks(&data);
+        assert_eq!(latin1["Creation Time"], "2024-05-06T07:08:09Z");
+        assert_eq!(latin1["Scan Run ID"], "run-1");
+        assert_eq!(latin1["Source Host"], EvidenceStamp::source_host());
+        assert!(latin1["Software"].starts_with(env!("CARGO_PKG_NAME")));
+        // Latin-1 无法表示的目标写入 iTXt
+        assert!(!latin1.contains_key("Target"));
+        assert_eq!(utf8["Target"], "http://例子.测试/登录");
+
+        let decoded = image::load_from_memory(&data).unwrap();
+        assert_eq!(
+            (decoded.width(), decoded.height()),
+            (footer.width(), footer.height())
+        );
+    }
+}
@@ -1,7 +1,16 @@
+pub mod address_bar;
 pub mod async_screenshot_service;
+pub mod capture_handle;
 pub mod concurrent_executor;
+pub mod evidence_stamp;
+pub mod page_capture;
+pub mod rate_limiter;
 pub mod screenshot_service;
+pub mod shutdown_signal;
+pub mod text_painter;
+pub mod thumbnailer;
 
 pub use async_screenshot_service::AsyncScreenshotService;
 pub use concurrent_executor::ConcurrentExecutor;
 pub use screenshot_service::ScreenshotService;
+pub use shutdown_signal::ShutdownSignal;
@@ -0,0 +1,331 @@
+use chrono::Utc;
+use headless_chrome::protocol::cdp::Network::{Headers, ResourceType};
+use headless_chrome::protocol::cdp::Page::CaptureScreenshotFormatOption;
+use headless_chrome::protocol::cdp::Security::SecurityState;
+use headless_chrome::protocol::cdp::types::Event;
+use headless_chrome::{Browser, LaunchOptions, Tab};
+use image::DynamicImage;
+use log::warn;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::analysis::content_tagger::ContentTagger;
+use crate::analysis::fingerprint::FingerprintEngine;
+use crate::analysis::page_classifier::PageClassifier;
+use crate::analysis::perceptual_hash::PerceptualHash;
+use crate::config::address_bar::AddressBarMode;
+use crate::config::app_config::AppConfig;
+use crate::file_io::output_naming::{NamingContext, OutputNaming};
+use crate::models::page_content::PageContent;
+use crate::models::page_response::{PageResponse, RedirectHop};
+use crate::models::screenshot_error::ScreenshotError;
+use crate::models::target::{ScreenshotResult, Target};
+use crate::screenshot::address_bar::AddressBar;
+use crate::screenshot::capture_handle::CaptureHandle;
+use crate::screenshot::evidence_stamp::EvidenceStamp;
+
+/// 单个目标的完整截图流程，供同步和异步截图服务共用
+///
+/// 依次完成导航、读取页面信息、技术识别和内容标签、绘制地址栏、截图、
+/// 计算感知哈希和页面分类、添加水印，最后按命名模板保存。
+pub struct PageCapture {
+    config: AppConfig,
+    fingerprints: FingerprintEngine,
+    frame_template: String,
+    naming: OutputNaming,
+}
+
+impl PageCapture {
+    /// 加载指纹规则、地址栏模板和命名模板，任一有误时返回错误
+    pub fn new(config: AppConfig) -> Result<Self, ScreenshotError> {
+        let fingerprints = FingerprintEngine::load(config.fingerprint_rules.as_deref())?;
+        let frame_template =
+            AddressBar::template(config.frame_theme, config.frame_template.as_deref())?;
+        let naming = OutputNaming::new(&config.naming_template)?;
+        Ok(Self {
+            config,
+            fingerprints,
+            frame_template,
+            naming,
+        })
+    }
+
+    /// 截取单个目标并保存，阻塞执行
+    ///
+    /// `handle` 用于在超过时限时由调用方强制结束本次截图所用的浏览器，已被终止的截图不会落盘。
+    pub fn capture(
+        &self,
+        target: &Target,
+        handle: &CaptureHandle,
+    ) -> Result<ScreenshotResult, ScreenshotError> {
+        let config = &self.config;
+
+        // 创建浏览器实例
+        let browser = Self::launch_browser(config)?;
+        handle.attach(&browser)?;
+
+        // 创建新标签页
+        let tab = Self::open_tab(&browser, config)?;
+
+        // 导航到目标URL
+        let response = Self::navigate(&tab, target)?;
+
+        // 等待页面加载完成
+        std::thread::sleep(std::time::Duration::from_secs(3));
+
+        // 读取页面标题
+        let title = tab.get_title().ok().filter(|title| !title.is_empty());
+
+        // 读取页面内容，用于截图后分类、内容标签和技术识别
+        let content = Self::inspect(&tab);
+        let technologies = self.fingerprints.detect(&response, &content);
+        let tags = ContentTagger::tag(title.as_deref(), &content);
+
+        // 添加地址栏，截图后绘制时不改动页面
+        if config.address_bar == AddressBarMode::Overlay {
+            AddressBar::inject(
+                &tab,
+                &self.frame_template,
+                &target.url,
+                title.as_deref(),
+                &response,
+            )?;
+        }
+
+        let mut screenshot_data =
+            tab.capture_screenshot(CaptureScreenshotFormatOption::Png, None, None, true)?;
+        let captured_at = Utc::now();
+
+        // 已超时的截图不再落盘，避免留下与日志不一致的文件
+        if handle.is_killed() {
+            return Err(ScreenshotError::Other("截图已被终止".to_string()));
+        }
+        let composite = config.address_bar == AddressBarMode::Composite;
+        let mut image = match image::load_from_memory(&screenshot_data) {
+            Ok(image) => Some(image),
+            // 地址栏和水印都画在解码后的图像上，不能保存缺少它们的截图
+            Err(e) if composite || config.watermark => {
+                return Err(ScreenshotError::Other(format!(
+                    "解析截图失败，无法添加地址栏或水印: {}",
+                    e
+                )));
+            }
+            Err(e) => {
+                warn!("解析截图失败 {}: {}", target.url, e);
+                None
+            }
+        };
+        // 地址栏和水印含URL和截图时间，哈希和分类只看页面本身，避免影响相似截图聚类和空白页识别
+        let perceptual_hash = image.as_ref().map(PerceptualHash::from_image);
+        let page_class = PageClassifier::classify(image.as_ref(), title.as_deref(), &content);
+
+        if composite && let Some(page) = &image {
+            let composed =
+                AddressBar::composite(page, &target.url, response.status_code, captured_at);
+            screenshot_data = AddressBar::encode_png(&composed)?;
+            image = Some(DynamicImage::ImageRgba8(composed));
+        }
+
+        if config.watermark
+            && let Some(page) = &image
+        {
+            let stamp = EvidenceStamp::new(&config.run_id, &target.url, captured_at);
+            screenshot_data = stamp.encode_png(&stamp.footer(page))?;
+        }
+
+        // 按命名模板保存，同名时自动加序号
+        let context = NamingContext {
+            target,
+            profile: &config.profile,
+            run_id: &config.run_id,
+            captured_at,
+            ext: "png",
+        };
+        let full_path = self
+            .naming
+            .save(&config.screenshots_dir, &context, &screenshot_data)?;
+
+        Ok(ScreenshotResult::success(target.clone(), full_path)
+            .with_response(response)
+            .with_title(title)
+            .with_perceptual_hash(perceptual_hash)
+            .with_page_class(page_class)
+            .with_technologies(technologies)
+            .with_tags(tags))
+    }
+
+    /// 启动浏览器实例
+    pub fn launch_browser(config: &AppConfig) -> Result<Browser, ScreenshotError> {
+        let launch_options = LaunchOptions::default_builder()
+            .headless(config.headless)
+            .window_size(Some(config.window_size))
+            .build()
+            .map_err(|e| ScreenshotError::BrowserLaunch(e.to_string()))?;
+
+        Browser::new(launch_options).map_err(|e| ScreenshotError::BrowserLaunch(format!("{:#}", e)))
+    }
+
+    /// 创建新标签页并设置用户代理
+    pub fn open_tab(browser: &Browser, config: &AppConfig) -> Result<Arc<Tab>, ScreenshotError> {
+        let tab = browser.new_tab()?;
+        tab.set_user_agent(&config.user_agent, None, None)?;
+        Ok(tab)
+    }
+
+    /// 导航到目标URL并等待导航完成，返回主文档的响应信息
+    pub fn navigate(tab: &Tab, target: &Target) -> Result<PageResponse, ScreenshotError> {
+        const HANDLER_NAME: &str = "main-document-response";
+
+        // 主框架的 frameId 与标签页的 targetId 相同，据此过滤掉 iframe 的文档请求
+        let main_frame_id = tab.get_target_id().clone();
+        let response = Arc::new(Mutex::new(PageResponse::default()));
+
+        let response_clone = Arc::clone(&response);
+        let frame_id = main_frame_id.clone();
+        tab.register_response_handling(
+            HANDLER_NAME,
+            Box::new(move |params, _fetch_body| {
+                if params.Type == ResourceType::Document
+                    && params.frame_id.as_deref() == Some(frame_id.as_str())
+                    && let Ok(mut response) = response_clone.lock()
+                {
+                    response.status_code = u16::try_from(params.response.status).ok();
+                    response.final_url = Some(params.response.url.clone());
+                    response.headers = Self::collect_headers(&params.response.headers);
+                    response.tls_valid = match params.response.security_state {
+                        SecurityState::Secure => Some(true),
+                        SecurityState::Insecure | SecurityState::InsecureBroken => Some(false),
+                        _ => None,
+                    };
+                }
+            }),
+        )?;
+
+        // 重定向响应不会触发 responseReceived，只能从下一次请求的 redirectResponse 中取得
+        let response_clone = Arc::clone(&response);
+        let redirect_listener = tab.add_event_listener(Arc::new(move |event: &Event| {
+            if let Event::NetworkRequestWillBeSent(event) = event
+                && event.params.Type == Some(ResourceType::Document)
+                && event.params.frame_id.as_deref() == Some(main_frame_id.as_str())
+                && let Some(redirect) = &event.params.redirect_response
+                && let Ok(mut response) = response_clone.lock()
+            {
+                response.redirect_chain.push(RedirectHop {
+                    url: redirect.url.clone(),
+                    status_code: u16::try_from(redirect.status).unwrap_or_default(),
+                });
+            }
+        }))?;
+
+        let navigation = tab
+            .navigate_to(&target.url)
+            .and_then(|tab| tab.wait_until_navigated());
+        tab.deregister_response_handling(HANDLER_NAME)?;
+        tab.remove_event_listener(&redirect_listener)?;
+        let response = response
+            .lock()
+            .map(|response| response.clone())
+            .unwrap_or_default();
+
+        match navigation {
+            Ok(_) => Ok(response),
+            Err(e) => {
+                let error = ScreenshotError::from(e);
+                // 服务器返回错误状态码且响应体为空时，Chrome 拒绝渲染页面
+                match (&error, response.status_code) {
+                    (ScreenshotError::Navigation(msg), Some(code))
+                        if msg.contains("net::ERR_HTTP_RESPONSE_CODE_FAILURE") =>
+                    {
+                        Err(ScreenshotError::HttpStatus(code))
+                    }
+                    _ => Err(error),
+                }
+            }
+        }
+    }
+
+    /// 读取页面地址、文本、HTML、脚本、meta 标签、Cookie 和密码输入框，并判断是否为 Chrome 错误页
+    ///
+    /// 读取失败时返回空内容，不影响截图。需在注入地址栏之前调用。
+    pub fn inspect(tab: &Tab) -> PageContent {
+        const INSPECT_SCRIPT: &str = r#"JSON.stringify({
+            url: location.href,
+            text: document.body ? document.body.innerText.slice(0, 4000) : "",
+            chrome_error: location.href.startsWith("chrome-error://")
+                || !!document.getElementById("main-frame-error")
+                || (!!document.body && document.body.classList.contains("neterror")),
+            html: document.documentElement.outerHTML.slice(0, 200000),
+            password_fields: document.querySelectorAll("input[type=password]").length,
+            script_srcs: Array.from(document.scripts).map(s => s.src).filter(Boolean).slice(0, 200),
+            meta: Array.from(document.querySelectorAll("meta[name]")).reduce((meta, m) => {
+                meta[m.getAttribute("name").toLowerCase()] = m.getAttribute("content") || "";
+                return meta;
+            }, {})
+        })"#;
+
+        let Some(serde_json::Value::String(json)) = tab
+            .evaluate(INSPECT_SCRIPT, false)
+            .ok()
+            .and_then(|object| object.value)
+        else {
+            return PageContent::default();
+        };
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(&json) else {
+            return PageContent::default();
+        };
+
+        let string_map = |value: &serde_json::Value| -> BTreeMap<String, String> {
+            value
+                .as_object()
+                .map(|map| {
+                    map.iter()
+                        .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        PageContent {
+            url: value["url"].as_str().unwrap_or_default().to_string(),
+            text: value["text"].as_str().unwrap_or_default().to_string(),
+            chrome_error: value["chrome_error"].as_bool().unwrap_or(false),
+            html: value["html"].as_str().unwrap_or_default().to_string(),
+            password_fields: value["password_fields"].as_u64().unwrap_or(0) as usize,
+            script_srcs: value["script_srcs"]
+                .as_array()
+                .map(|srcs| {
+                    srcs.iter()
+                        .filter_map(|src| src.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            meta: string_map(&value["meta"]),
+            cookies: tab
+                .get_cookies()
+                .map(|cookies| {
+                    cookies
+                        .into_iter()
+                        .map(|cookie| (cookie.name, cookie.value))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// 将 CDP 响应头转换为小写键名的有序表
+    fn collect_headers(headers: &Headers) -> BTreeMap<String, String> {
+        let Some(serde_json::Value::Object(map)) = &headers.0 else {
+            return BTreeMap::new();
+        };
+
+        map.iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(value) => value.clone(),
+                    other => other.to_string(),
+                };
+                (name.to_ascii_lowercase(), value)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+use crate::config::rate_limit::RateLimitConfig;
+use crate::models::target::Target;
+
+/// 截图限速器
+///
+/// 包含三层限制：全局每秒请求数、同一主机的并发数以及同一主机两次访问的最小间隔。
+/// 主机按解析出的IP归并，同一IP上的多个虚拟主机共享限额；解析失败时按主机名归并。
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    next_global_slot: Mutex<Option<Instant>>,
+    hosts: Mutex<HashMap<String, Arc<HostState>>>,
+    resolved: Mutex<HashMap<String, String>>,
+}
+
+/// 单个主机的限速状态
+struct HostState {
+    semaphore: Option<Arc<Semaphore>>,
+    next_slot: Mutex<Option<Instant>>,
+}
+
+/// 主机并发许可，释放后同一主机的其他目标才能开始
+pub struct HostPermit {
+    key: String,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            next_global_slot: Mutex::new(None),
+            hosts: Mutex::new(HashMap::new()),
+            resolved: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 是否配置了任何限速
+    pub fn is_enabled(&self) -> bool {
+        self.config.global_interval().is_some()
+            || self.config.per_host_concurrency.is_some()
+            || !self.config.per_host_delay().is_zero()
+    }
+
+    /// 获取目标所在主机的并发许可
+    pub async fn acquire_host(&self, target: &Target) -> HostPermit {
+        let key = self.host_key(target).await;
+        let state = self.host_state(&key);
+        let permit = match &state.semaphore {
+            Some(semaphore) => Arc::clone(semaphore).acquire_owned().await.ok(),
+            None => None,
+        };
+
+        HostPermit {
+            key,
+            _permit: permit,
+        }
+    }
+
+    /// 等待轮到该主机发起下一次截图，同时满足全局速率和主机间隔限制
+    pub async fn wait_turn(&self, permit: &HostPermit) {
+        let host_delay = self.config.per_host_delay();
+        if !host_delay.is_zero() {
+            let state = self.host_state(&permit.key);
+            let slot = Self::reserve_slot(&state.next_slot, host_delay);
+            tokio::time::sleep_until(slot).await;
+        }
+
+        if let Some(interval) = self.config.global_interval() {
+            let slot = Self::reserve_slot(&self.next_global_slot, interval);
+            tokio::time::sleep_until(slot).await;
+        }
+    }
+
+    /// 给定各主机剩余的目标数，估算限速条件下完成所需的最短时间
+    pub fn minimum_remaining_time(&self, remaining_total: usize, max_per_host: usize) -> Duration {
+        let global = self
+            .config
+            .global_interval()
+            .map(|interval| interval.mul_f64(remaining_total as f64))
+            .unwrap_or_default();
+        let per_host = self
+            .config
+            .per_host_delay()
+            .mul_f64(max_per_host.saturating_sub(1) as f64);
+
+        global.max(per_host)
+    }
+
+    /// 预约下一个可用的时间点，返回本次应等待到的时刻
+    fn reserve_slot(next_slot: &Mutex<Option<Instant>>, interval: Duration) -> Instant {
+        let now = Instant::now();
+        let mut next_slot = next_slot.lock().unwrap_or_else(|e| e.into_inner());
+        let slot = next_slot.map_or(now, |next| next.max(now));
+        *next_slot = Some(slot + interval);
+        slot
+    }
+
+    fn host_state(&self, key: &str) -> Arc<HostState> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let state = hosts.entry(key.to_string()).or_insert_with(|| {
+            Arc::new(HostState {
+                semaphore: self
+                    .config
+                    .per_host_concurrency
+                    .map(|limit| Arc::new(Semaphore::new(limit.max(1)))),
+                next_slot: Mutex::new(None),
+            })
+        });
+        Arc::clone(state)
+    }
+
+    /// 计算目标的限速分组键，优先使用解析出的IP；未启用限速时直接使用主机名
+    ///
+    /// 解析结果会被缓存，同一主机只解析一次。
+    pub async fn host_key(&self, target: &Target) -> String {
+        let (host, port) = target.get_host_and_port();
+        if !self.is_enabled() || host.parse::<IpAddr>().is_ok() {
+            return host;
+        }
+
+        if let Some(key) = self
+            .resolved
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&host)
+        {
+            return key.clone();
+        }
+
+        let key = match tokio::net::lookup_host((host.as_str(), port)).await {
+            Ok(mut addrs) => addrs
+                .next()
+                .map(|addr| addr.ip().to_string())
+                .unwrap_or_else(|| host.clone()),
+            Err(_) => host.clone(),
+        };
+        self.resolved
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(host, key.clone());
+        key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(url: &str) -> Target {
+        Target::new(url.to_string()).unwrap()
+    }
+
+    fn limiter(per_host_concurrency: Option<usize>, per_host_delay_ms: u64) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            requests_per_second: Some(2.0),
+            per_host_concurrency,
+            per_host_delay_ms,
+        })
+    }
+
+    #[test]
+    fn remaining_time_is_bounded_by_the_slowest_limit() {
+        let limiter = limiter(None, 3000);
+        // 全局 2 次/秒：10 个目标至少 5 秒
+        assert_eq!(
+            limiter.minimum_remaining_time(10, 2),
+            Duration::from_secs(5)
+        );
+        // 单个主机 4 个目标，间隔 3 秒：至少 9 秒
+        assert_eq!(limiter.minimum_remaining_time(4, 4), Duration::from_secs(9));
+        assert_eq!(limiter.minimum_remaining_time(0, 0), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn host_key_uses_ip_literals_and_skips_lookup_when_disabled() {
+        let limiter = limiter(Some(1), 0);
+        assert_eq!(
+            limiter.host_key(&target("http://192.0.2.1:8080/a")).await,
+            "192.0.2.1"
+        );
+        assert_eq!(limiter.host_key(&target("http://[::1]/")).await, "::1");
+
+        let disabled = RateLimiter::new(RateLimitConfig::default());
+        assert!(!disabled.is_enabled());
+        assert_eq!(
+            disabled.host_key(&target("https://Example.invalid/")).await,
+            "example.invalid"
+        );
+    }
+
+    /// 50 毫秒内能否拿到主机许可
+    async fn acquires(limiter: &RateLimiter, url: &str) -> bool {
+        let target = target(url);
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire_host(&target))
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn per_host_concurrency_is_shared_by_the_same_key() {
+        let limiter = limiter(Some(1), 0);
+        let first = limiter.acquire_host(&target("http://192.0.2.1/a")).await;
+
+        assert!(!acquires(&limiter, "http://192.0.2.1:8443/b").await);
+        assert!(acquires(&limiter, "http://192.0.2.2/").await);
+
+        drop(first);
+        assert!(acquires(&limiter, "http://192.0.2.1/c").await);
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+/// 停止信号
+///
+/// 触发后执行器不再调度新的目标，进行中的截图在宽限期结束后被强制终止。
+#[derive(Clone, Default)]
+pub struct ShutdownSignal {
+    triggered: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 触发停止信号，唤醒所有等待者
+    pub fn trigger(&self) {
+        self.triggered.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// 是否已触发
+    pub fn is_triggered(&self) -> bool {
+        self.triggered.load(Ordering::SeqCst)
+    }
+
+    /// 等待停止信号触发
+    pub async fn wait(&self) {
+        loop {
+            // 先注册通知再检查标志，避免错过两者之间发生的触发
+            let notified = self.notify.notified();
+            if self.is_triggered() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+use image::{Rgba, RgbaImage};
+
+/// 内置字体，覆盖拉丁、希腊和西里尔字母，许可见 fonts/LICENSE-DejaVu
+const FONT_DATA: &[u8] = include_bytes!("fonts/DejaVuSansMono.ttf");
+
+/// 在截图上绘制单行文字
+pub struct TextPainter {
+    font: FontRef<'static>,
+}
+
+impl Default for TextPainter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextPainter {
+    pub fn new() -> Self {
+        Self {
+            font: FontRef::try_from_slice(FONT_DATA).expect("内置字体格式错误"),
+        }
+    }
+
+    /// 文字宽度（像素）
+    pub fn width(&self, text: &str, size: f32) -> f32 {
+        let font = self.font.as_scaled(PxScale::from(size));
+        text.chars().map(|c| font.h_advance(font.glyph_id(c))).sum()
+    }
+
+    /// 宽度超过 `max_width` 时从末尾截断并加上省略号
+    pub fn fit(&self, text: &str, size: f32, max_width: f32) -> String {
+        if self.width(text, size) <= max_width {
+            return text.to_string();
+        }
+
+        let mut fitted = String::new();
+        let budget = max_width - self.width("…", size);
+        for c in text.chars() {
+            fitted.push(c);
+            if self.width(&fitted, size) > budget {
+                fitted.pop();
+                break;
+            }
+        }
+        fitted.push('…');
+        fitted
+    }
+
+    /// 以 (`x`, `y`) 为文字框左上角绘制，超出图片的部分被裁掉
+    pub fn draw(
+        &self,
+        image: &mut RgbaImage,
+        text: &str,
+        x: i32,
+        y: i32,
+        size: f32,
+        color: Rgba<u8>,
+    ) {
+        let font = self.font.as_scaled(PxScale::from(size));
+        let baseline = y as f32 + font.ascent();
+        let mut caret = x as f32;
+
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            let glyph = id.with_scale_and_position(PxScale::from(size), point(caret, baseline));
+            caret += font.h_advance(id);
+
+            let Some(outline) = self.font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px >= image.width() as i32 || py >= image.height() as i32 {
+                    return;
+                }
+                Self::blend(image.get_pixel_mut(px as u32, py as u32), color, coverage);
+            });
+        }
+    }
+
+    /// 按覆盖率把文字颜色叠加到像素上
+    fn blend(pixel: &mut Rgba<u8>, color: Rgba<u8>, coverage: f32) {
+        let alpha = coverage.clamp(0.0, 1.0) * f32::from(color[3]) / 255.0;
+        for channel in 0..3 {
+            let base = f32::from(pixel[channel]);
+            pixel[channel] = (base + (f32::from(color[channel]) - base) * alpha).round() as u8;
+        }
+        pixel[3] = pixel[3].max((alpha * 255.0) as u8);
+    }
+}
@@ -0,0 +1,194 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ExtendedColorType};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::oneshot;
+
+use crate::config::thumbnail::{ThumbnailConfig, ThumbnailFormat};
+use crate::models::screenshot_error::ScreenshotError;
+use crate::utils::path_utils::PathUtils;
+
+/// 缩略图目录名
+pub const THUMBNAILS_DIRNAME: &str = "thumbs";
+
+/// 缩略图最大高宽比，整页截图只保留顶部，约为默认 iPhone 视口的比例
+const MAX_ASPECT_RATIO: f64 = 2.2;
+
+/// JPEG 缩略图质量
+const JPEG_QUALITY: u8 = 80;
+
+/// 缩略图生成器
+///
+/// 缩放和编码在独立的 CPU 线程池中进行，不占用浏览器的并发许可和 tokio 的阻塞线程。
+/// 缩略图放在输出目录的 `thumbs/` 下，子目录结构与截图相同。
+pub struct Thumbnailer {
+    config: ThumbnailConfig,
+    screenshots_dir: String,
+    pool: ThreadPool,
+}
+
+impl Thumbnailer {
+    pub fn new(config: ThumbnailConfig, screenshots_dir: &str) -> Result<Self, ScreenshotError> {
+        let pool = ThreadPoolBuilder::new()
+            .thread_name(|index| format!("thumbnail-{}", index))
+            .build()
+            .map_err(|e| ScreenshotError::Other(format!("创建缩略图线程池失败: {}", e)))?;
+        Ok(Self {
+            config,
+            screenshots_dir: screenshots_dir.to_string(),
+            pool,
+        })
+    }
+
+    /// 为截图文件生成缩略图，返回缩略图路径
+    pub async fn generate(&self, screenshot_path: &str) -> Result<String, ScreenshotError> {
+        let source = PathBuf::from(screenshot_path);
+        let destination = self.thumbnail_path(screenshot_path);
+        let config = self.config.clone();
+
+        let (sender, receiver) = oneshot::channel();
+        self.pool.spawn(move || {
+            let _ = sender.send(Self::write(&source, &destination, &config).map(|_| destination));
+        });
+        let destination = receiver
+            .await
+            .map_err(|_| ScreenshotError::Other("缩略图任务异常退出".to_string()))??;
+        Ok(destination.to_string_lossy().to_string())
+    }
+
+    /// 截图对应的缩略图路径：`thumbs/<截图相对路径>`，扩展名换为缩略图格式
+    fn thumbnail_path(&self, screenshot_path: &str) -> PathBuf {
+        let relative = PathUtils::relative_to(&self.screenshots_dir, screenshot_path);
+        let mut path = Path::new(&self.screenshots_dir).join(THUMBNAILS_DIRNAME);
+        path.extend(relative.split(['/', '\\']).filter(|part| !part.is_empty()));
+        path.set_extension(self.config.format.extension());
+        path
+    }
+
+    /// 读取截图，缩放后写入缩略图文件
+    fn write(
+        source: &Path,
+        destination: &Path,
+        config: &ThumbnailConfig,
+    ) -> Result<(), ScreenshotError> {
+        let image = image::open(source)
+            .map_err(|e| ScreenshotError::Other(format!("解析截图失败: {}", e)))?;
+        let data = Self::encode(&Self::resize(&image, config.width), config.format)?;
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(destination, data)?;
+        Ok(())
+    }
+
+    /// 缩放到指定宽度，过长的整页截图先裁掉底部，窄于指定宽度时不放大
+    fn resize(image: &DynamicImage, width: u32) -> DynamicImage {
+        let max_height = (f64::from(image.width()) * MAX_ASPECT_RATIO).ceil() as u32;
+        let cropped = if image.height() > max_height {
+            image.crop_imm(0, 0, image.width(), max_height)
+        } else {
+            image.clone()
+        };
+
+        let width = width.min(cropped.width()).max(1);
+        let height = ((u64::from(cropped.height()) * u64::from(width))
+            / u64::from(cropped.width().max(1)))
+        .max(1) as u32;
+        cropped.resize_exact(width, height, FilterType::Triangle)
+    }
+
+    fn encode(image: &DynamicImage, format: ThumbnailFormat) -> Result<Vec<u8>, ScreenshotError> {
+        let to_error =
+            |e: image::ImageError| ScreenshotError::Other(format!("编码缩略图失败: {}", e));
+
+        let mut data = Vec::new();
+        match format {
+            // JPEG 不支持透明通道
+            ThumbnailFormat::Jpeg => {
+                let rgb = image.to_rgb8();
+                JpegEncoder::new_with_quality(&mut data, JPEG_QUALITY)
+                    .encode(&rgb, rgb.width(), rgb.height(), ExtendedColorType::Rgb8)
+                    .map_err(to_error)?;
+            }
+            ThumbnailFormat::Webp => {
+                let rgba = image.to_rgba8();
+                WebPEncoder::new_lossless(&mut data)
+                    .encode(&rgba, rgba.width(), rgba.height(), ExtendedColorType::Rgba8)
+                    .map_err(to_error)?;
+            }
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    fn screenshots_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("picmyweb2-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("example.com")).unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    fn screenshot(dir: &str, name: &str, width: u32, height: u32) -> String {
+        let path = Path::new(dir).join(name);
+        RgbImage::from_pixel(width, height, image::Rgb([40, 120, 200]))
+            .save(&path)
+            .unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[tokio::test]
+    async fn thumbnails_are_scaled_cropped_and_encoded() {
+        let dir = screenshots_dir("thumbs-tall");
+        // 整页截图高度远超宽度的 2.2 倍，缩略图只保留顶部
+        let tall = screenshot(&dir, "example.com/page.png", 400, 2000);
+        let config = ThumbnailConfig {
+            width: 100,
+            format: ThumbnailFormat::Jpeg,
+        };
+        let thumbnailer = Thumbnailer::new(config, &dir).unwrap();
+
+        let path = thumbnailer.generate(&tall).await.unwrap();
+        assert_eq!(
+            Path::new(&path),
+            Path::new(&dir).join("thumbs/example.com/page.jpg")
+        );
+        let data = fs::read(&path).unwrap();
+        assert_eq!(image::guess_format(&data).unwrap(), ImageFormat::Jpeg);
+        let thumbnail = image::load_from_memory(&data).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (100, 220));
+    }
+
+    #[tokio::test]
+    async fn narrow_screenshots_are_not_upscaled() {
+        let dir = screenshots_dir("thumbs-narrow");
+        let narrow = screenshot(&dir, "narrow.png", 60, 30);
+        let config = ThumbnailConfig {
+            width: 320,
+            format: ThumbnailFormat::Webp,
+        };
+        let thumbnailer = Thumbnailer::new(config, &dir).unwrap();
+
+        let path = thumbnailer.generate(&narrow).await.unwrap();
+        assert!(path.ends_with("narrow.webp"), "{}", path);
+        let data = fs::read(&path).unwrap();
+        assert_eq!(image::guess_format(&data).unwrap(), ImageFormat::WebP);
+        let thumbnail = image::load_from_memory(&data).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (60, 30));
+
+        assert!(
+            thumbnailer
+                .generate(&format!("{}/missing.png", dir))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
 /// 路径工具函数
 pub struct PathUtils;
 
@@ -23,4 +26,213 @@ impl PathUtils {
         }
         Ok(())
     }
+
+    /// 获取文件所在目录，同时识别 '/' 和 '\' 分隔符，没有目录部分时返回 "."
+    pub fn parent_dir(path: &str) -> String {
+        match path.rfind(['/', '\\']) {
+            Some(0) => path[..1].to_string(),
+            Some(index) => path[..index].to_string(),
+            None => ".".to_string(),
+        }
+    }
+
+    /// 获取路径中的文件名部分，同时识别 '/' 和 '\' 分隔符
+    pub fn file_name(path: &str) -> &str {
+        path.rsplit(['/', '\\']).next().unwrap_or(path)
+    }
+
+    /// 计算文件相对于目录的路径，不在该目录下时返回原路径
+    pub fn relative_to(base: &str, path: &str) -> String {
+        let base = base.trim_end_matches(['/', '\\']);
+        match path.strip_prefix(base) {
+            Some(rest) if rest.starts_with(['/', '\\']) => {
+                rest.trim_start_matches(['/', '\\']).to_string()
+            }
+            _ => path.to_string(),
+        }
+    }
+
+    /// 计算从目录 `base` 指向 `path` 的相对路径，用于报告中的截图链接
+    ///
+    /// 两者先转为绝对路径（文件存在时解析符号链接）再逐级比较，不在 `base` 下时生成 `../` 开头的路径；
+    /// 除根目录外没有共同的上级目录时（例如 Windows 的不同盘符）返回绝对路径，链接改为 `file:` URL。
+    pub fn relative_path(base: &str, path: &str) -> String {
+        // 其他系统上记录的绝对路径（例如在 Linux 上读取 Windows 生成的日志）原样返回
+        if !Path::new(path).is_absolute() && !Self::file_url_prefix(path).0.is_empty() {
+            return path.to_string();
+        }
+        let (Some(base), Some(target)) = Self::absolute_pair(base, path) else {
+            return path.to_string();
+        };
+
+        let base: Vec<Component> = base.components().collect();
+        let target: Vec<Component> = target.components().collect();
+        let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+        let root_only = base
+            .iter()
+            .take(common)
+            .all(|part| matches!(part, Component::Prefix(_) | Component::RootDir));
+        if root_only {
+            let absolute = target.iter().collect::<PathBuf>();
+            let absolute = absolute.to_string_lossy();
+            // Windows 规范化路径带有 `\\?\` 前缀，去掉后才能转换为 `file:` URL
+            return match absolute.strip_prefix(r"\\?\") {
+                Some(rest) => match rest.strip_prefix(r"UNC\") {
+                    Some(share) => format!(r"\\{}", share),
+                    None => rest.to_string(),
+                },
+                None => absolute.to_string(),
+            };
+        }
+
+        let mut relative = PathBuf::new();
+        for _ in common..base.len() {
+            relative.push("..");
+        }
+        relative.extend(&target[common..]);
+        relative.to_string_lossy().to_string()
+    }
+
+    /// 把两个路径转为可比较的绝对路径：都存在时使用规范化路径，否则按当前目录补全并去掉 `.` 和 `..`
+    fn absolute_pair(base: &str, path: &str) -> (Option<PathBuf>, Option<PathBuf>) {
+        if let (Ok(base), Ok(path)) = (fs::canonicalize(base), fs::canonicalize(path)) {
+            return (Some(base), Some(path));
+        }
+        let absolute = |path: &str| {
+            let path = std::path::absolute(path).ok()?;
+            let mut normalized = PathBuf::new();
+            for component in path.components() {
+                match component {
+                    Component::CurDir => {}
+                    Component::ParentDir => {
+                        normalized.pop();
+                    }
+                    other => normalized.push(other),
+                }
+            }
+            Some(normalized)
+        };
+        (absolute(base), absolute(path))
+    }
+
+    /// 将路径转换为可用于 HTML/Markdown 链接的 URL 路径
+    ///
+    /// 绝对路径（`/x`、`C:\x`、`\\server\share`）转换为 `file:` URL，盘符后的冒号不编码。
+    pub fn to_url_path(path: &str) -> String {
+        let (mut encoded, path) = Self::file_url_prefix(path);
+        for c in path.chars() {
+            match c {
+                '\\' => encoded.push('/'),
+                'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' | '/' => encoded.push(c),
+                _ => {
+                    let mut buf = [0u8; 4];
+                    for byte in c.encode_utf8(&mut buf).bytes() {
+                        encoded.push_str(&format!("%{:02X}", byte));
+                    }
+                }
+            }
+        }
+        encoded
+    }
+
+    /// 拆出绝对路径对应的 `file:` URL 前缀和剩余部分，相对路径没有前缀
+    fn file_url_prefix(path: &str) -> (String, &str) {
+        let bytes = path.as_bytes();
+        if path.starts_with("\\\\") || path.starts_with("//") {
+            ("file:".to_string(), path)
+        } else if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+            (format!("file:///{}:", &path[..1]), &path[2..])
+        } else if path.starts_with(['/', '\\']) {
+            ("file://".to_string(), path)
+        } else {
+            (String::new(), path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_paths_are_percent_encoded() {
+        assert_eq!(
+            PathUtils::to_url_path("sub\\a b#1:2.png"),
+            "sub/a%20b%231%3A2.png"
+        );
+        assert_eq!(PathUtils::to_url_path("截图.png"), "%E6%88%AA%E5%9B%BE.png");
+    }
+
+    #[test]
+    fn absolute_paths_become_file_urls() {
+        assert_eq!(
+            PathUtils::to_url_path("C:\\shots\\a b.png"),
+            "file:///C:/shots/a%20b.png"
+        );
+        assert_eq!(
+            PathUtils::to_url_path("/tmp/shots/a.png"),
+            "file:///tmp/shots/a.png"
+        );
+        assert_eq!(
+            PathUtils::to_url_path("\\\\server\\share\\a.png"),
+            "file://server/share/a.png"
+        );
+    }
+
+    #[test]
+    fn relative_to_strips_the_base_directory() {
+        assert_eq!(
+            PathUtils::relative_to("out/", "out\\sub\\a.png"),
+            "sub\\a.png"
+        );
+        assert_eq!(
+            PathUtils::relative_to("out", "outside/a.png"),
+            "outside/a.png"
+        );
+        assert_eq!(PathUtils::relative_to("out", "/abs/a.png"), "/abs/a.png");
+    }
+
+    #[test]
+    fn relative_path_walks_up_from_the_report_directory() {
+        let root = std::env::temp_dir().join(format!("picmyweb2-links-{}", std::process::id()));
+        fs::create_dir_all(root.join("shots").join("sub")).unwrap();
+        fs::create_dir_all(root.join("other")).unwrap();
+        let shot = root.join("shots").join("sub").join("a.png");
+        fs::write(&shot, b"png").unwrap();
+        let shot = shot.to_string_lossy().to_string();
+        let dir = |name: &str| root.join(name).to_string_lossy().to_string();
+
+        let expected = Path::new("..").join("shots").join("sub").join("a.png");
+        assert_eq!(
+            PathUtils::relative_path(&dir("other"), &shot),
+            expected.to_string_lossy()
+        );
+        let with_dot = format!("{}/./", dir("shots"));
+        assert_eq!(
+            PathUtils::relative_path(&with_dot, &shot),
+            Path::new("sub").join("a.png").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn relative_path_handles_missing_and_foreign_paths() {
+        assert_eq!(
+            PathUtils::relative_path(
+                "/nonexistent/run/./report",
+                "/nonexistent/run/shots/../a.png"
+            ),
+            Path::new("..").join("a.png").to_string_lossy()
+        );
+        assert_eq!(
+            PathUtils::relative_path("out", "C:\\shots\\a.png"),
+            "C:\\shots\\a.png"
+        );
+        if cfg!(unix) {
+            // 只有根目录相同时使用绝对路径
+            assert_eq!(
+                PathUtils::relative_path("/nonexistent/report", "/elsewhere/a.png"),
+                "/elsewhere/a.png"
+            );
+        }
+    }
 }